@@ -1 +1,559 @@
 pub use beacon_api_types::*;
+
+pub mod registration;
+pub mod unblind;
+pub mod verify;
+
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+use ssz::{Decode, DecodeError};
+use ssz_derive::{Decode, Encode};
+use tree_hash_derive::TreeHash;
+use types::{
+    superstruct, BlindedPayload, BlobsList, ChainSpec, EthSpec, ExecutionBlockHash,
+    ExecutionPayloadBellatrix, ExecutionPayloadCapella, ExecutionPayloadDeneb,
+    ExecutionPayloadElectra, ExecutionPayloadHeaderBellatrix, ExecutionPayloadHeaderCapella,
+    ExecutionPayloadHeaderDeneb, ExecutionPayloadHeaderElectra, ExecutionPayloadHeaderRef,
+    ForkName, Hash256, KzgCommitments, KzgProofs, PublicKeyBytes, SecretKey, Signature,
+    SignedBeaconBlockBellatrix, SignedBeaconBlockCapella, SignedBeaconBlockDeneb,
+    SignedBeaconBlockElectra, SignedBlindedBeaconBlock, SignedRoot, Slot, Uint256,
+};
+
+/// Header with the fork of the body of an SSZ request or response.
+pub const CONSENSUS_VERSION_HEADER: &str = "Eth-Consensus-Version";
+
+/// Fork of a blinded block, as sent in the `Eth-Consensus-Version` header.
+pub fn blinded_block_fork_name<E: EthSpec>(block: &SignedBlindedBeaconBlock<E>) -> ForkName {
+    match block {
+        SignedBlindedBeaconBlock::Base(_) => ForkName::Base,
+        SignedBlindedBeaconBlock::Altair(_) => ForkName::Altair,
+        SignedBlindedBeaconBlock::Bellatrix(_) => ForkName::Bellatrix,
+        SignedBlindedBeaconBlock::Capella(_) => ForkName::Capella,
+        SignedBlindedBeaconBlock::Deneb(_) => ForkName::Deneb,
+        SignedBlindedBeaconBlock::Electra(_) => ForkName::Electra,
+    }
+}
+
+/// Types with one variant per fork whose encodings do not identify the fork, so decoding needs
+/// the fork from elsewhere, e.g. the `version` field or the `Eth-Consensus-Version` header.
+pub trait ForkVersionDecode: Sized {
+    fn from_json_value_by_fork(
+        value: serde_json::Value,
+        fork_name: ForkName,
+    ) -> Result<Self, serde_json::Error>;
+
+    fn from_ssz_bytes_by_fork(bytes: &[u8], fork_name: ForkName) -> Result<Self, DecodeError>;
+}
+
+macro_rules! impl_fork_version_decode {
+    ($ty:ident, $bellatrix:ident, $capella:ident, $deneb:ident, $electra:ident) => {
+        impl<E: EthSpec> ForkVersionDecode for $ty<E> {
+            fn from_json_value_by_fork(
+                value: serde_json::Value,
+                fork_name: ForkName,
+            ) -> Result<Self, serde_json::Error> {
+                match fork_name {
+                    ForkName::Bellatrix => serde_json::from_value(value).map(Self::Bellatrix),
+                    ForkName::Capella => serde_json::from_value(value).map(Self::Capella),
+                    ForkName::Deneb => serde_json::from_value(value).map(Self::Deneb),
+                    ForkName::Electra => serde_json::from_value(value).map(Self::Electra),
+                    fork_name => Err(serde_json::Error::custom(format!(
+                        "{} is not supported before Bellatrix, got {fork_name}",
+                        stringify!($ty)
+                    ))),
+                }
+            }
+
+            fn from_ssz_bytes_by_fork(
+                bytes: &[u8],
+                fork_name: ForkName,
+            ) -> Result<Self, DecodeError> {
+                match fork_name {
+                    ForkName::Bellatrix => {
+                        <$bellatrix<E>>::from_ssz_bytes(bytes).map(Self::Bellatrix)
+                    }
+                    ForkName::Capella => <$capella<E>>::from_ssz_bytes(bytes).map(Self::Capella),
+                    ForkName::Deneb => <$deneb<E>>::from_ssz_bytes(bytes).map(Self::Deneb),
+                    ForkName::Electra => <$electra<E>>::from_ssz_bytes(bytes).map(Self::Electra),
+                    fork_name => Err(DecodeError::BytesInvalid(format!(
+                        "{} is not supported before Bellatrix, got {fork_name}",
+                        stringify!($ty)
+                    ))),
+                }
+            }
+        }
+    };
+}
+
+impl<E: EthSpec> ForkVersionDecode for SignedBlindedBeaconBlock<E> {
+    fn from_json_value_by_fork(
+        value: serde_json::Value,
+        fork_name: ForkName,
+    ) -> Result<Self, serde_json::Error> {
+        match fork_name {
+            ForkName::Bellatrix => {
+                serde_json::from_value::<SignedBeaconBlockBellatrix<E, BlindedPayload<E>>>(value)
+                    .map(Self::Bellatrix)
+            }
+            ForkName::Capella => {
+                serde_json::from_value::<SignedBeaconBlockCapella<E, BlindedPayload<E>>>(value)
+                    .map(Self::Capella)
+            }
+            ForkName::Deneb => {
+                serde_json::from_value::<SignedBeaconBlockDeneb<E, BlindedPayload<E>>>(value)
+                    .map(Self::Deneb)
+            }
+            ForkName::Electra => {
+                serde_json::from_value::<SignedBeaconBlockElectra<E, BlindedPayload<E>>>(value)
+                    .map(Self::Electra)
+            }
+            fork_name => Err(serde_json::Error::custom(format!(
+                "blinded blocks are not supported before Bellatrix, got {fork_name}"
+            ))),
+        }
+    }
+
+    fn from_ssz_bytes_by_fork(bytes: &[u8], fork_name: ForkName) -> Result<Self, DecodeError> {
+        match fork_name {
+            ForkName::Bellatrix => {
+                SignedBeaconBlockBellatrix::<E, BlindedPayload<E>>::from_ssz_bytes(bytes)
+                    .map(Self::Bellatrix)
+            }
+            ForkName::Capella => {
+                SignedBeaconBlockCapella::<E, BlindedPayload<E>>::from_ssz_bytes(bytes)
+                    .map(Self::Capella)
+            }
+            ForkName::Deneb => {
+                SignedBeaconBlockDeneb::<E, BlindedPayload<E>>::from_ssz_bytes(bytes)
+                    .map(Self::Deneb)
+            }
+            ForkName::Electra => {
+                SignedBeaconBlockElectra::<E, BlindedPayload<E>>::from_ssz_bytes(bytes)
+                    .map(Self::Electra)
+            }
+            fork_name => Err(DecodeError::BytesInvalid(format!(
+                "blinded blocks are not supported before Bellatrix, got {fork_name}"
+            ))),
+        }
+    }
+}
+
+// Builder API requests
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GetHeaderParams {
+    pub slot: Slot,
+    pub parent_hash: ExecutionBlockHash,
+    pub pubkey: PublicKeyBytes,
+}
+
+// Builder API responses
+
+#[superstruct(
+    variants(Bellatrix, Capella, Deneb, Electra),
+    variant_attributes(
+        derive(
+            Debug,
+            Clone,
+            PartialEq,
+            Serialize,
+            Deserialize,
+            Encode,
+            Decode,
+            TreeHash
+        ),
+        serde(bound = "E: EthSpec", deny_unknown_fields),
+    )
+)]
+#[derive(Debug, Clone, PartialEq, Serialize, Encode, TreeHash)]
+#[serde(bound = "E: EthSpec", untagged)]
+#[ssz(enum_behaviour = "transparent")]
+#[tree_hash(enum_behaviour = "transparent")]
+pub struct BuilderBid<E: EthSpec> {
+    #[superstruct(only(Bellatrix), partial_getter(rename = "header_bellatrix"))]
+    pub header: ExecutionPayloadHeaderBellatrix<E>,
+    #[superstruct(only(Capella), partial_getter(rename = "header_capella"))]
+    pub header: ExecutionPayloadHeaderCapella<E>,
+    #[superstruct(only(Deneb), partial_getter(rename = "header_deneb"))]
+    pub header: ExecutionPayloadHeaderDeneb<E>,
+    #[superstruct(only(Electra), partial_getter(rename = "header_electra"))]
+    pub header: ExecutionPayloadHeaderElectra<E>,
+    #[superstruct(only(Deneb, Electra))]
+    pub blob_kzg_commitments: KzgCommitments<E>,
+    #[serde(with = "serde_utils::quoted_u256")]
+    pub value: Uint256,
+    pub pubkey: PublicKeyBytes,
+}
+
+impl<E: EthSpec> BuilderBid<E> {
+    pub fn header(&self) -> ExecutionPayloadHeaderRef<'_, E> {
+        self.to_ref().header()
+    }
+
+    pub fn fork_name(&self) -> ForkName {
+        match self {
+            Self::Bellatrix(_) => ForkName::Bellatrix,
+            Self::Capella(_) => ForkName::Capella,
+            Self::Deneb(_) => ForkName::Deneb,
+            Self::Electra(_) => ForkName::Electra,
+        }
+    }
+
+    /// Sign the bid with the relay's `secret_key`.
+    pub fn sign(self, secret_key: &SecretKey, spec: &ChainSpec) -> SignedBuilderBid<E> {
+        let signature = secret_key.sign(self.signing_root(spec.get_builder_domain()));
+        match self {
+            Self::Bellatrix(message) => {
+                SignedBuilderBid::Bellatrix(SignedBuilderBidBellatrix { message, signature })
+            }
+            Self::Capella(message) => {
+                SignedBuilderBid::Capella(SignedBuilderBidCapella { message, signature })
+            }
+            Self::Deneb(message) => {
+                SignedBuilderBid::Deneb(SignedBuilderBidDeneb { message, signature })
+            }
+            Self::Electra(message) => {
+                SignedBuilderBid::Electra(SignedBuilderBidElectra { message, signature })
+            }
+        }
+    }
+}
+
+impl<'a, E: EthSpec> BuilderBidRef<'a, E> {
+    pub fn header(&self) -> ExecutionPayloadHeaderRef<'a, E> {
+        match *self {
+            Self::Bellatrix(bid) => ExecutionPayloadHeaderRef::Bellatrix(&bid.header),
+            Self::Capella(bid) => ExecutionPayloadHeaderRef::Capella(&bid.header),
+            Self::Deneb(bid) => ExecutionPayloadHeaderRef::Deneb(&bid.header),
+            Self::Electra(bid) => ExecutionPayloadHeaderRef::Electra(&bid.header),
+        }
+    }
+
+    /// Root signed by the relay, with `domain` the builder domain of the chain.
+    pub fn signing_root(&self, domain: Hash256) -> Hash256 {
+        match *self {
+            Self::Bellatrix(bid) => bid.signing_root(domain),
+            Self::Capella(bid) => bid.signing_root(domain),
+            Self::Deneb(bid) => bid.signing_root(domain),
+            Self::Electra(bid) => bid.signing_root(domain),
+        }
+    }
+}
+
+impl<E: EthSpec> SignedRoot for BuilderBid<E> {}
+impl<E: EthSpec> SignedRoot for BuilderBidBellatrix<E> {}
+impl<E: EthSpec> SignedRoot for BuilderBidCapella<E> {}
+impl<E: EthSpec> SignedRoot for BuilderBidDeneb<E> {}
+impl<E: EthSpec> SignedRoot for BuilderBidElectra<E> {}
+
+impl_fork_version_decode!(
+    BuilderBid,
+    BuilderBidBellatrix,
+    BuilderBidCapella,
+    BuilderBidDeneb,
+    BuilderBidElectra
+);
+
+#[superstruct(
+    variants(Bellatrix, Capella, Deneb, Electra),
+    variant_attributes(
+        derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode),
+        serde(bound = "E: EthSpec", deny_unknown_fields),
+    )
+)]
+#[derive(Debug, Clone, PartialEq, Serialize, Encode)]
+#[serde(bound = "E: EthSpec", untagged)]
+#[ssz(enum_behaviour = "transparent")]
+pub struct SignedBuilderBid<E: EthSpec> {
+    #[superstruct(flatten)]
+    pub message: BuilderBid<E>,
+    pub signature: Signature,
+}
+
+impl<E: EthSpec> SignedBuilderBid<E> {
+    pub fn message(&self) -> BuilderBidRef<'_, E> {
+        match self {
+            Self::Bellatrix(bid) => BuilderBidRef::Bellatrix(&bid.message),
+            Self::Capella(bid) => BuilderBidRef::Capella(&bid.message),
+            Self::Deneb(bid) => BuilderBidRef::Deneb(&bid.message),
+            Self::Electra(bid) => BuilderBidRef::Electra(&bid.message),
+        }
+    }
+
+    pub fn fork_name(&self) -> ForkName {
+        match self {
+            Self::Bellatrix(_) => ForkName::Bellatrix,
+            Self::Capella(_) => ForkName::Capella,
+            Self::Deneb(_) => ForkName::Deneb,
+            Self::Electra(_) => ForkName::Electra,
+        }
+    }
+}
+
+impl_fork_version_decode!(
+    SignedBuilderBid,
+    SignedBuilderBidBellatrix,
+    SignedBuilderBidCapella,
+    SignedBuilderBidDeneb,
+    SignedBuilderBidElectra
+);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode)]
+#[serde(bound = "E: EthSpec", deny_unknown_fields)]
+pub struct BlobsBundle<E: EthSpec> {
+    pub commitments: KzgCommitments<E>,
+    pub proofs: KzgProofs<E>,
+    #[serde(with = "ssz_types::serde_utils::list_of_hex_fixed_vec")]
+    pub blobs: BlobsList<E>,
+}
+
+#[superstruct(
+    variants(Deneb, Electra),
+    variant_attributes(
+        derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode),
+        serde(bound = "E: EthSpec", deny_unknown_fields),
+    )
+)]
+#[derive(Debug, Clone, PartialEq, Serialize, Encode)]
+#[serde(bound = "E: EthSpec", untagged)]
+#[ssz(enum_behaviour = "transparent")]
+pub struct ExecutionPayloadAndBlobsBundle<E: EthSpec> {
+    #[superstruct(only(Deneb), partial_getter(rename = "execution_payload_deneb"))]
+    pub execution_payload: ExecutionPayloadDeneb<E>,
+    #[superstruct(only(Electra), partial_getter(rename = "execution_payload_electra"))]
+    pub execution_payload: ExecutionPayloadElectra<E>,
+    pub blobs_bundle: BlobsBundle<E>,
+}
+
+/// `data` of a submit blinded block response: the execution payload before Deneb, and the
+/// execution payload with its blobs bundle from Deneb.
+#[derive(Debug, Clone, PartialEq, Serialize, Encode)]
+#[serde(bound = "E: EthSpec", untagged)]
+#[ssz(enum_behaviour = "transparent")]
+pub enum PayloadResponse<E: EthSpec> {
+    Bellatrix(ExecutionPayloadBellatrix<E>),
+    Capella(ExecutionPayloadCapella<E>),
+    Deneb(ExecutionPayloadAndBlobsBundleDeneb<E>),
+    Electra(ExecutionPayloadAndBlobsBundleElectra<E>),
+}
+
+impl<E: EthSpec> PayloadResponse<E> {
+    pub fn fork_name(&self) -> ForkName {
+        match self {
+            Self::Bellatrix(_) => ForkName::Bellatrix,
+            Self::Capella(_) => ForkName::Capella,
+            Self::Deneb(_) => ForkName::Deneb,
+            Self::Electra(_) => ForkName::Electra,
+        }
+    }
+}
+
+impl_fork_version_decode!(
+    PayloadResponse,
+    ExecutionPayloadBellatrix,
+    ExecutionPayloadCapella,
+    ExecutionPayloadAndBlobsBundleDeneb,
+    ExecutionPayloadAndBlobsBundleElectra
+);
+
+/// A response with the fork of its `data` in `version`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VersionedResponse<T> {
+    pub version: ForkName,
+    pub data: T,
+}
+
+impl<'de, T: ForkVersionDecode> Deserialize<'de> for VersionedResponse<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Helper {
+            version: ForkName,
+            data: serde_json::Value,
+        }
+
+        let helper = Helper::deserialize(deserializer)?;
+        let data =
+            T::from_json_value_by_fork(helper.data, helper.version).map_err(D::Error::custom)?;
+        Ok(Self {
+            version: helper.version,
+            data,
+        })
+    }
+}
+
+// Builder API response types
+pub type GetHeaderResponse<E> = VersionedResponse<SignedBuilderBid<E>>;
+pub type SubmitBlindedBlockResponse<E> = VersionedResponse<PayloadResponse<E>>;
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+
+    use ssz::Encode as _;
+    use types::{Keypair, MainnetEthSpec};
+
+    use super::*;
+
+    type E = MainnetEthSpec;
+
+    const FORKS: [ForkName; 4] = [
+        ForkName::Bellatrix,
+        ForkName::Capella,
+        ForkName::Deneb,
+        ForkName::Electra,
+    ];
+
+    fn bid(fork_name: ForkName) -> BuilderBid<E> {
+        let value = Uint256::from(100);
+        let pubkey = PublicKeyBytes::empty();
+        match fork_name {
+            ForkName::Bellatrix => BuilderBid::Bellatrix(BuilderBidBellatrix {
+                header: Default::default(),
+                value,
+                pubkey,
+            }),
+            ForkName::Capella => BuilderBid::Capella(BuilderBidCapella {
+                header: Default::default(),
+                value,
+                pubkey,
+            }),
+            ForkName::Deneb => BuilderBid::Deneb(BuilderBidDeneb {
+                header: Default::default(),
+                blob_kzg_commitments: Default::default(),
+                value,
+                pubkey,
+            }),
+            ForkName::Electra => BuilderBid::Electra(BuilderBidElectra {
+                header: Default::default(),
+                blob_kzg_commitments: Default::default(),
+                value,
+                pubkey,
+            }),
+            fork_name => panic!("no bids at {fork_name}"),
+        }
+    }
+
+    fn payload(fork_name: ForkName) -> PayloadResponse<E> {
+        let blobs_bundle = BlobsBundle {
+            commitments: Default::default(),
+            proofs: Default::default(),
+            blobs: Default::default(),
+        };
+        match fork_name {
+            ForkName::Bellatrix => PayloadResponse::Bellatrix(Default::default()),
+            ForkName::Capella => PayloadResponse::Capella(Default::default()),
+            ForkName::Deneb => PayloadResponse::Deneb(ExecutionPayloadAndBlobsBundleDeneb {
+                execution_payload: Default::default(),
+                blobs_bundle,
+            }),
+            ForkName::Electra => PayloadResponse::Electra(ExecutionPayloadAndBlobsBundleElectra {
+                execution_payload: Default::default(),
+                blobs_bundle,
+            }),
+            fork_name => panic!("no payloads at {fork_name}"),
+        }
+    }
+
+    /// Decode `value` from JSON and SSZ given `fork_name`, and from a JSON versioned response.
+    fn round_trip<T>(value: T, fork_name: ForkName)
+    where
+        T: ForkVersionDecode + Serialize + ssz::Encode + Clone + PartialEq + Debug,
+    {
+        let json = serde_json::to_value(&value).unwrap();
+        assert_eq!(T::from_json_value_by_fork(json, fork_name).unwrap(), value);
+        let bytes = value.as_ssz_bytes();
+        assert_eq!(T::from_ssz_bytes_by_fork(&bytes, fork_name).unwrap(), value);
+
+        let response = VersionedResponse {
+            version: fork_name,
+            data: value,
+        };
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(
+            serde_json::from_str::<VersionedResponse<T>>(&json).unwrap(),
+            response
+        );
+    }
+
+    #[test]
+    fn fork_version_round_trips() {
+        let keypair = Keypair::random();
+        let spec = E::default_spec();
+        for fork_name in FORKS {
+            assert_eq!(bid(fork_name).fork_name(), fork_name);
+            assert_eq!(payload(fork_name).fork_name(), fork_name);
+            round_trip(bid(fork_name), fork_name);
+            round_trip(bid(fork_name).sign(&keypair.sk, &spec), fork_name);
+            round_trip(payload(fork_name), fork_name);
+        }
+    }
+
+    #[test]
+    fn wrong_fork_rejected() {
+        // Capella headers have a withdrawals root Bellatrix headers do not.
+        let json = serde_json::to_value(bid(ForkName::Capella)).unwrap();
+        assert!(BuilderBid::<E>::from_json_value_by_fork(json, ForkName::Bellatrix).is_err());
+        let bytes = payload(ForkName::Capella).as_ssz_bytes();
+        assert!(PayloadResponse::<E>::from_ssz_bytes_by_fork(&bytes, ForkName::Bellatrix).is_err());
+
+        let json = format!(
+            r#"{{"version":"bellatrix","data":{}}}"#,
+            serde_json::to_string(&payload(ForkName::Capella)).unwrap()
+        );
+        assert!(serde_json::from_str::<SubmitBlindedBlockResponse<E>>(&json).is_err());
+    }
+
+    #[test]
+    fn versioned_response_fields() {
+        let data = serde_json::to_string(
+            &bid(ForkName::Capella).sign(&Keypair::random().sk, &E::default_spec()),
+        )
+        .unwrap();
+        for json in [
+            format!(r#"{{"data":{data}}}"#),
+            r#"{"version":"capella"}"#.to_owned(),
+            format!(r#"{{"version":"paris","data":{data}}}"#),
+        ] {
+            assert!(
+                serde_json::from_str::<GetHeaderResponse<E>>(&json).is_err(),
+                "{json}"
+            );
+        }
+
+        let json = format!(r#"{{"version":"capella","data":{data}}}"#);
+        let response = serde_json::from_str::<GetHeaderResponse<E>>(&json).unwrap();
+        assert_eq!(response.version, ForkName::Capella);
+        assert_eq!(response.data.fork_name(), ForkName::Capella);
+    }
+
+    #[test]
+    fn pre_bellatrix_rejected() {
+        let json = serde_json::to_value(bid(ForkName::Bellatrix)).unwrap();
+        let bytes = bid(ForkName::Bellatrix).as_ssz_bytes();
+        for fork_name in [ForkName::Base, ForkName::Altair] {
+            let e = BuilderBid::<E>::from_json_value_by_fork(json.clone(), fork_name).unwrap_err();
+            assert!(
+                e.to_string().contains("not supported before Bellatrix"),
+                "{e}"
+            );
+            assert!(matches!(
+                BuilderBid::<E>::from_ssz_bytes_by_fork(&bytes, fork_name),
+                Err(DecodeError::BytesInvalid(_))
+            ));
+            assert!(SignedBlindedBeaconBlock::<E>::from_json_value_by_fork(
+                json.clone(),
+                fork_name
+            )
+            .is_err());
+            assert!(matches!(
+                SignedBlindedBeaconBlock::<E>::from_ssz_bytes_by_fork(&bytes, fork_name),
+                Err(DecodeError::BytesInvalid(_))
+            ));
+
+            let response = format!(r#"{{"version":"{fork_name}","data":{json}}}"#);
+            assert!(serde_json::from_str::<SubmitBlindedBlockResponse<E>>(&response).is_err());
+        }
+    }
+}
@@ -0,0 +1,362 @@
+use std::{borrow::Cow, fmt};
+
+use bls::{verify_signature_sets, SignatureSet};
+use ssz::{Decode, DecodeError, Encode};
+use types::{
+    ChainSpec, Hash256, PublicKeyBytes, SignedRoot, SignedValidatorRegistrationData,
+    ValidatorRegistrationData,
+};
+
+/// Default number of seconds a registration timestamp may be ahead of the local clock.
+pub const DEFAULT_MAX_FUTURE_SECS: u64 = 10;
+
+/// Why a batch of registrations was rejected. `index` is the position of the offending
+/// registration in the batch.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegistrationError {
+    InvalidPubkey {
+        index: usize,
+    },
+    InvalidSignature {
+        index: usize,
+    },
+    FutureTimestamp {
+        index: usize,
+        timestamp: u64,
+        max: u64,
+    },
+    /// The registration is older than the one it replaces.
+    TimestampRegression {
+        index: usize,
+        timestamp: u64,
+        previous: u64,
+    },
+    /// The batch failed to verify although every signature verified on its own.
+    BatchVerificationFailed,
+}
+
+impl RegistrationError {
+    /// Position of the offending registration in the batch, if a single one is to blame.
+    pub fn index(&self) -> Option<usize> {
+        match self {
+            Self::InvalidPubkey { index }
+            | Self::InvalidSignature { index }
+            | Self::FutureTimestamp { index, .. }
+            | Self::TimestampRegression { index, .. } => Some(*index),
+            Self::BatchVerificationFailed => None,
+        }
+    }
+}
+
+impl fmt::Display for RegistrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPubkey { index } => {
+                write!(f, "registration {index} has an invalid pubkey")
+            }
+            Self::InvalidSignature { index } => {
+                write!(f, "registration {index} has an invalid signature")
+            }
+            Self::FutureTimestamp {
+                index,
+                timestamp,
+                max,
+            } => write!(
+                f,
+                "registration {index} has timestamp {timestamp}, later than the maximum {max}"
+            ),
+            Self::TimestampRegression {
+                index,
+                timestamp,
+                previous,
+            } => write!(
+                f,
+                "registration {index} has timestamp {timestamp}, before the previous {previous}"
+            ),
+            Self::BatchVerificationFailed => {
+                write!(f, "registration signatures failed to verify as a batch")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegistrationError {}
+
+/// Registrations of `registrations` that differ from the previous registration of their
+/// validator, as returned by `previous`. Unchanged registrations were verified before and can
+/// be skipped.
+pub fn changed_registrations<'a, F>(
+    registrations: &'a [SignedValidatorRegistrationData],
+    mut previous: F,
+) -> Vec<&'a SignedValidatorRegistrationData>
+where
+    F: FnMut(&PublicKeyBytes) -> Option<ValidatorRegistrationData>,
+{
+    registrations
+        .iter()
+        .filter(|registration| {
+            previous(&registration.message.pubkey)
+                .is_none_or(|previous| previous != registration.message)
+        })
+        .collect()
+}
+
+/// Checks batches of validator registrations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RegistrationVerifier {
+    builder_domain: Hash256,
+    max_future_secs: u64,
+}
+
+impl RegistrationVerifier {
+    pub fn new(spec: &ChainSpec) -> Self {
+        Self {
+            builder_domain: spec.get_builder_domain(),
+            max_future_secs: DEFAULT_MAX_FUTURE_SECS,
+        }
+    }
+
+    /// Accept timestamps up to `max_future_secs` ahead of the local clock.
+    pub fn with_max_future_secs(mut self, max_future_secs: u64) -> Self {
+        self.max_future_secs = max_future_secs;
+        self
+    }
+
+    /// Check that no registration is from the future, as of `now` in seconds since the Unix
+    /// epoch, or older than the previous registration of its validator, then verify all
+    /// signatures in one batch.
+    pub fn verify<F>(
+        &self,
+        registrations: &[&SignedValidatorRegistrationData],
+        now: u64,
+        mut previous: F,
+    ) -> Result<(), RegistrationError>
+    where
+        F: FnMut(&PublicKeyBytes) -> Option<ValidatorRegistrationData>,
+    {
+        let max = now + self.max_future_secs;
+        for (index, registration) in registrations.iter().enumerate() {
+            let timestamp = registration.message.timestamp;
+            if timestamp > max {
+                return Err(RegistrationError::FutureTimestamp {
+                    index,
+                    timestamp,
+                    max,
+                });
+            }
+            if let Some(previous) = previous(&registration.message.pubkey) {
+                if timestamp < previous.timestamp {
+                    return Err(RegistrationError::TimestampRegression {
+                        index,
+                        timestamp,
+                        previous: previous.timestamp,
+                    });
+                }
+            }
+        }
+
+        self.verify_signatures(registrations)
+    }
+
+    /// Verify the signatures of `registrations` in one batch, falling back to one at a time to
+    /// find the invalid one if the batch fails.
+    pub fn verify_signatures(
+        &self,
+        registrations: &[&SignedValidatorRegistrationData],
+    ) -> Result<(), RegistrationError> {
+        if registrations.is_empty() {
+            return Ok(());
+        }
+
+        let pubkeys = registrations
+            .iter()
+            .enumerate()
+            .map(|(index, registration)| {
+                registration
+                    .message
+                    .pubkey
+                    .decompress()
+                    .map_err(|_| RegistrationError::InvalidPubkey { index })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let signing_roots = registrations
+            .iter()
+            .map(|registration| registration.message.signing_root(self.builder_domain))
+            .collect::<Vec<_>>();
+
+        let signature_sets = registrations
+            .iter()
+            .zip(&pubkeys)
+            .zip(&signing_roots)
+            .map(|((registration, pubkey), signing_root)| {
+                SignatureSet::single_pubkey(
+                    &registration.signature,
+                    Cow::Borrowed(pubkey),
+                    *signing_root,
+                )
+            })
+            .collect::<Vec<_>>();
+        if verify_signature_sets(signature_sets.iter()) {
+            return Ok(());
+        }
+
+        let index = registrations
+            .iter()
+            .zip(&pubkeys)
+            .zip(&signing_roots)
+            .position(|((registration, pubkey), signing_root)| {
+                !registration.signature.verify(pubkey, *signing_root)
+            });
+        Err(match index {
+            Some(index) => RegistrationError::InvalidSignature { index },
+            None => RegistrationError::BatchVerificationFailed,
+        })
+    }
+}
+
+/// SSZ encoding of a `POST /eth/v1/builder/validators` body, a list of registrations.
+pub fn registrations_as_ssz_bytes(registrations: &[SignedValidatorRegistrationData]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(
+        registrations.len() * <SignedValidatorRegistrationData as Encode>::ssz_fixed_len(),
+    );
+    for registration in registrations {
+        registration.ssz_append(&mut bytes);
+    }
+    bytes
+}
+
+/// Decode a list of registrations encoded with [`registrations_as_ssz_bytes`].
+pub fn registrations_from_ssz_bytes(
+    bytes: &[u8],
+) -> Result<Vec<SignedValidatorRegistrationData>, DecodeError> {
+    let len = <SignedValidatorRegistrationData as Decode>::ssz_fixed_len();
+    if bytes.len() % len != 0 {
+        return Err(DecodeError::InvalidByteLength {
+            len: bytes.len(),
+            expected: (bytes.len() / len).max(1) * len,
+        });
+    }
+    bytes
+        .chunks(len)
+        .map(SignedValidatorRegistrationData::from_ssz_bytes)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use types::{Address, Keypair};
+
+    use super::*;
+
+    fn registration(
+        keypair: &Keypair,
+        spec: &ChainSpec,
+        timestamp: u64,
+    ) -> SignedValidatorRegistrationData {
+        let message = ValidatorRegistrationData {
+            fee_recipient: Address::repeat_byte(1),
+            gas_limit: 30_000_000,
+            timestamp,
+            pubkey: keypair.pk.compress(),
+        };
+        let signature = keypair
+            .sk
+            .sign(message.signing_root(spec.get_builder_domain()));
+        SignedValidatorRegistrationData { message, signature }
+    }
+
+    #[test]
+    fn verify_batch() {
+        let spec = ChainSpec::mainnet();
+        let verifier = RegistrationVerifier::new(&spec);
+        let keypairs = (0..4).map(|_| Keypair::random()).collect::<Vec<_>>();
+        let mut registrations = keypairs
+            .iter()
+            .map(|keypair| registration(keypair, &spec, 100))
+            .collect::<Vec<_>>();
+
+        let all = registrations.iter().collect::<Vec<_>>();
+        assert_eq!(verifier.verify(&all, 100, |_| None), Ok(()));
+        assert_eq!(
+            verifier.verify(&all, 80, |_| None),
+            Err(RegistrationError::FutureTimestamp {
+                index: 0,
+                timestamp: 100,
+                max: 90,
+            })
+        );
+        let newer = registration(&keypairs[0], &spec, 101).message;
+        assert_eq!(
+            verifier.verify(&all, 100, |_| Some(newer.clone())),
+            Err(RegistrationError::TimestampRegression {
+                index: 0,
+                timestamp: 100,
+                previous: 101,
+            })
+        );
+
+        registrations[2].message.gas_limit += 1;
+        let all = registrations.iter().collect::<Vec<_>>();
+        assert_eq!(
+            verifier.verify(&all, 100, |_| None),
+            Err(RegistrationError::InvalidSignature { index: 2 })
+        );
+        assert_eq!(
+            RegistrationError::InvalidSignature { index: 2 }.to_string(),
+            "registration 2 has an invalid signature"
+        );
+        assert_eq!(
+            RegistrationError::InvalidSignature { index: 2 }.index(),
+            Some(2)
+        );
+        assert_eq!(RegistrationError::BatchVerificationFailed.index(), None);
+    }
+
+    #[test]
+    fn skip_unchanged() {
+        let spec = ChainSpec::mainnet();
+        let keypairs = (0..3).map(|_| Keypair::random()).collect::<Vec<_>>();
+        let registrations = keypairs
+            .iter()
+            .map(|keypair| registration(keypair, &spec, 100))
+            .collect::<Vec<_>>();
+
+        let changed = changed_registrations(&registrations, |pubkey| {
+            registrations
+                .iter()
+                .find(|registration| registration.message.pubkey == *pubkey)
+                .filter(|registration| registration.message.pubkey != keypairs[1].pk.compress())
+                .map(|registration| registration.message.clone())
+        });
+        assert_eq!(changed, vec![&registrations[1]]);
+    }
+
+    #[test]
+    fn ssz_list_round_trip() {
+        let spec = ChainSpec::mainnet();
+        let registrations = (0..3)
+            .map(|_| registration(&Keypair::random(), &spec, 100))
+            .collect::<Vec<_>>();
+
+        let bytes = registrations_as_ssz_bytes(&registrations);
+        assert_eq!(bytes, registrations.as_ssz_bytes());
+        assert_eq!(registrations_from_ssz_bytes(&bytes), Ok(registrations));
+        // Lengths are reported against the nearest shorter whole number of registrations, or
+        // a single one.
+        let len = <SignedValidatorRegistrationData as Decode>::ssz_fixed_len();
+        assert_eq!(
+            registrations_from_ssz_bytes(&bytes[1..]),
+            Err(DecodeError::InvalidByteLength {
+                len: 3 * len - 1,
+                expected: 2 * len,
+            })
+        );
+        assert_eq!(
+            registrations_from_ssz_bytes(&bytes[..len - 1]),
+            Err(DecodeError::InvalidByteLength {
+                len: len - 1,
+                expected: len,
+            })
+        );
+    }
+}
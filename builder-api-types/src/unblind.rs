@@ -0,0 +1,285 @@
+use std::fmt;
+
+use tree_hash::TreeHash;
+use types::{
+    blob_sidecar::BlobSidecarError, BlobSidecar, BlobSidecarList, EthSpec, ExecPayload,
+    ExecutionPayload, ExecutionPayloadHeader, ForkName, Hash256, SignedBeaconBlock,
+};
+
+use crate::{blinded_block_fork_name, BlobsBundle, PayloadResponse, SignedBlindedBeaconBlock};
+
+/// Why a payload could not be put into a blinded block.
+#[derive(Debug)]
+pub enum UnblindError {
+    /// The payload is for another fork than the block.
+    ForkMismatch {
+        block: ForkName,
+        payload: ForkName,
+    },
+    /// The block is for a fork without execution payloads.
+    UnsupportedFork(ForkName),
+    /// The payload's header root is not the root of the header in the block.
+    PayloadMismatch {
+        expected: Hash256,
+        got: Hash256,
+    },
+    /// The blobs bundle commits to other blobs than the block.
+    BlobCommitmentsMismatch,
+    /// The blobs bundle does not have one blob and proof per commitment.
+    BlobsBundleLengthMismatch {
+        commitments: usize,
+        proofs: usize,
+        blobs: usize,
+    },
+    BlobSidecar(BlobSidecarError),
+}
+
+impl fmt::Display for UnblindError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ForkMismatch { block, payload } => {
+                write!(f, "payload is for {payload}, block is for {block}")
+            }
+            Self::UnsupportedFork(fork_name) => {
+                write!(f, "{fork_name} blocks have no execution payload")
+            }
+            Self::PayloadMismatch { expected, got } => write!(
+                f,
+                "payload header root {got:?} does not match block header root {expected:?}"
+            ),
+            Self::BlobCommitmentsMismatch => {
+                write!(f, "blob commitments do not match the block")
+            }
+            Self::BlobsBundleLengthMismatch {
+                commitments,
+                proofs,
+                blobs,
+            } => write!(
+                f,
+                "blobs bundle has {proofs} proofs and {blobs} blobs for {commitments} commitments"
+            ),
+            Self::BlobSidecar(e) => write!(f, "unable to build blob sidecars: {e:?}"),
+        }
+    }
+}
+
+impl std::error::Error for UnblindError {}
+
+impl From<BlobSidecarError> for UnblindError {
+    fn from(e: BlobSidecarError) -> Self {
+        UnblindError::BlobSidecar(e)
+    }
+}
+
+/// Put the execution payload returned for `block` into it, checking the payload is the one the
+/// block commits to.
+pub fn unblind_block<E: EthSpec>(
+    block: SignedBlindedBeaconBlock<E>,
+    payload: PayloadResponse<E>,
+) -> Result<SignedBeaconBlock<E>, UnblindError> {
+    unblind(block, payload).map(|(block, _)| block)
+}
+
+/// Like [`unblind_block`], also returning the blob sidecars to publish with the block. There
+/// are none before Deneb.
+pub fn unblind_block_and_blobs<E: EthSpec>(
+    block: SignedBlindedBeaconBlock<E>,
+    payload: PayloadResponse<E>,
+) -> Result<(SignedBeaconBlock<E>, BlobSidecarList<E>), UnblindError> {
+    let (block, blobs_bundle) = unblind(block, payload)?;
+    let blob_sidecars = match blobs_bundle {
+        Some(bundle) => BlobSidecar::build_sidecars(bundle.blobs, &block, bundle.proofs)?,
+        None => BlobSidecarList::default(),
+    };
+    Ok((block, blob_sidecars))
+}
+
+fn unblind<E: EthSpec>(
+    block: SignedBlindedBeaconBlock<E>,
+    payload: PayloadResponse<E>,
+) -> Result<(SignedBeaconBlock<E>, Option<BlobsBundle<E>>), UnblindError> {
+    let block_fork = blinded_block_fork_name(&block);
+    if payload.fork_name() != block_fork {
+        return Err(UnblindError::ForkMismatch {
+            block: block_fork,
+            payload: payload.fork_name(),
+        });
+    }
+
+    let (execution_payload, blobs_bundle) = match payload {
+        PayloadResponse::Bellatrix(payload) => (ExecutionPayload::Bellatrix(payload), None),
+        PayloadResponse::Capella(payload) => (ExecutionPayload::Capella(payload), None),
+        PayloadResponse::Deneb(payload) => (
+            ExecutionPayload::Deneb(payload.execution_payload),
+            Some(payload.blobs_bundle),
+        ),
+        PayloadResponse::Electra(payload) => (
+            ExecutionPayload::Electra(payload.execution_payload),
+            Some(payload.blobs_bundle),
+        ),
+    };
+
+    let body = block.message().body();
+    let expected = body
+        .execution_payload()
+        .map_err(|_| UnblindError::UnsupportedFork(block_fork))?
+        .to_execution_payload_header()
+        .tree_hash_root();
+    let got = ExecutionPayloadHeader::from(execution_payload.to_ref()).tree_hash_root();
+    if expected != got {
+        return Err(UnblindError::PayloadMismatch { expected, got });
+    }
+
+    if let Some(bundle) = &blobs_bundle {
+        let commitments = body
+            .blob_kzg_commitments()
+            .map_err(|_| UnblindError::UnsupportedFork(block_fork))?;
+        if *commitments != bundle.commitments {
+            return Err(UnblindError::BlobCommitmentsMismatch);
+        }
+        if bundle.proofs.len() != commitments.len() || bundle.blobs.len() != commitments.len() {
+            return Err(UnblindError::BlobsBundleLengthMismatch {
+                commitments: commitments.len(),
+                proofs: bundle.proofs.len(),
+                blobs: bundle.blobs.len(),
+            });
+        }
+    }
+
+    let block = block
+        .try_into_full_block(Some(execution_payload))
+        .ok_or(UnblindError::UnsupportedFork(block_fork))?;
+    Ok((block, blobs_bundle))
+}
+
+#[cfg(test)]
+mod tests {
+    use types::{
+        BeaconBlock, BeaconBlockCapella, BeaconBlockDeneb, BlindedPayload, BlindedPayloadCapella,
+        BlindedPayloadDeneb, ChainSpec, EmptyBlock, ExecutionBlockHash, ExecutionPayloadBellatrix,
+        ExecutionPayloadCapella, ExecutionPayloadDeneb, KzgCommitment, KzgCommitments, KzgProof,
+        MainnetEthSpec, Signature,
+    };
+
+    use super::*;
+    use crate::ExecutionPayloadAndBlobsBundleDeneb;
+
+    type E = MainnetEthSpec;
+
+    fn capella_payload(block_hash: u8) -> ExecutionPayloadCapella<E> {
+        ExecutionPayloadCapella {
+            block_hash: ExecutionBlockHash::from_root(Hash256::repeat_byte(block_hash)),
+            ..Default::default()
+        }
+    }
+
+    fn capella_block(payload: &ExecutionPayloadCapella<E>) -> SignedBlindedBeaconBlock<E> {
+        let mut block = BeaconBlockCapella::<E, BlindedPayload<E>>::empty(&ChainSpec::mainnet());
+        block.body.execution_payload = BlindedPayloadCapella {
+            execution_payload_header: payload.into(),
+        };
+        SignedBeaconBlock::from_block(BeaconBlock::Capella(block), Signature::empty())
+    }
+
+    fn commitments(bytes: &[u8]) -> KzgCommitments<E> {
+        KzgCommitments::<E>::new(
+            bytes
+                .iter()
+                .map(|byte| KzgCommitment([*byte; 48]))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    /// A Deneb block committing to one blob, with the payload it is for.
+    fn deneb_block() -> (SignedBlindedBeaconBlock<E>, ExecutionPayloadDeneb<E>) {
+        let payload = ExecutionPayloadDeneb::<E> {
+            block_hash: ExecutionBlockHash::from_root(Hash256::repeat_byte(1)),
+            ..Default::default()
+        };
+        let mut block = BeaconBlockDeneb::<E, BlindedPayload<E>>::empty(&ChainSpec::mainnet());
+        block.body.execution_payload = BlindedPayloadDeneb {
+            execution_payload_header: (&payload).into(),
+        };
+        block.body.blob_kzg_commitments = commitments(&[1]);
+        let block = SignedBeaconBlock::from_block(BeaconBlock::Deneb(block), Signature::empty());
+        (block, payload)
+    }
+
+    fn deneb_payload(
+        payload: ExecutionPayloadDeneb<E>,
+        commitments: KzgCommitments<E>,
+        proofs: usize,
+    ) -> PayloadResponse<E> {
+        PayloadResponse::Deneb(ExecutionPayloadAndBlobsBundleDeneb {
+            execution_payload: payload,
+            blobs_bundle: BlobsBundle {
+                commitments,
+                proofs: vec![KzgProof([0; 48]); proofs].into(),
+                blobs: Default::default(),
+            },
+        })
+    }
+
+    #[test]
+    fn matching_payload() {
+        let payload = capella_payload(1);
+        let block = unblind_block(
+            capella_block(&payload),
+            PayloadResponse::Capella(payload.clone()),
+        )
+        .unwrap();
+        assert_eq!(
+            block
+                .message()
+                .body()
+                .execution_payload()
+                .unwrap()
+                .block_hash(),
+            payload.block_hash
+        );
+    }
+
+    #[test]
+    fn fork_mismatch() {
+        let block = capella_block(&capella_payload(1));
+        assert!(matches!(
+            unblind_block(
+                block,
+                PayloadResponse::Bellatrix(ExecutionPayloadBellatrix::default())
+            ),
+            Err(UnblindError::ForkMismatch {
+                block: ForkName::Capella,
+                payload: ForkName::Bellatrix,
+            })
+        ));
+    }
+
+    #[test]
+    fn payload_mismatch() {
+        let block = capella_block(&capella_payload(1));
+        assert!(matches!(
+            unblind_block(block, PayloadResponse::Capella(capella_payload(2))),
+            Err(UnblindError::PayloadMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn blobs_bundle_mismatch() {
+        let (block, payload) = deneb_block();
+        assert!(matches!(
+            unblind_block(block, deneb_payload(payload, commitments(&[2]), 1)),
+            Err(UnblindError::BlobCommitmentsMismatch)
+        ));
+
+        let (block, payload) = deneb_block();
+        assert!(matches!(
+            unblind_block(block, deneb_payload(payload, commitments(&[1]), 0)),
+            Err(UnblindError::BlobsBundleLengthMismatch {
+                commitments: 1,
+                proofs: 0,
+                blobs: 0,
+            })
+        ));
+    }
+}
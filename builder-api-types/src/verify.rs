@@ -0,0 +1,209 @@
+use std::fmt;
+
+use types::{ChainSpec, EthSpec, ExecutionBlockHash, Hash256, PublicKey, PublicKeyBytes, Uint256};
+
+use crate::{GetHeaderParams, SignedBuilderBid};
+
+/// Why a bid was rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BidError {
+    /// The bid names another key than the relay's.
+    UnexpectedPubkey {
+        expected: PublicKeyBytes,
+        got: PublicKeyBytes,
+    },
+    InvalidSignature,
+    /// The header is for another slot, going by its timestamp.
+    WrongTimestamp {
+        expected: u64,
+        got: u64,
+    },
+    WrongParentHash {
+        expected: ExecutionBlockHash,
+        got: ExecutionBlockHash,
+    },
+    ZeroValue,
+}
+
+impl fmt::Display for BidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedPubkey { expected, got } => {
+                write!(f, "bid is from {got}, expected the relay's key {expected}")
+            }
+            Self::InvalidSignature => write!(f, "bid signature is invalid"),
+            Self::WrongTimestamp { expected, got } => {
+                write!(f, "header timestamp is {got}, expected {expected}")
+            }
+            Self::WrongParentHash { expected, got } => {
+                write!(f, "header parent hash is {got:?}, expected {expected:?}")
+            }
+            Self::ZeroValue => write!(f, "bid has zero value"),
+        }
+    }
+}
+
+impl std::error::Error for BidError {}
+
+/// Checks bids returned by relays for a get header request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BidVerifier {
+    builder_domain: Hash256,
+    genesis_time: Option<u64>,
+    seconds_per_slot: u64,
+}
+
+impl BidVerifier {
+    pub fn new(spec: &ChainSpec) -> Self {
+        Self {
+            builder_domain: spec.get_builder_domain(),
+            genesis_time: None,
+            seconds_per_slot: spec.seconds_per_slot,
+        }
+    }
+
+    /// Check header timestamps against the slot requested. They are not checked without a
+    /// genesis time.
+    pub fn with_genesis_time(mut self, genesis_time: u64) -> Self {
+        self.genesis_time = Some(genesis_time);
+        self
+    }
+
+    /// Check that `bid` names `relay_pubkey` and is signed by it.
+    pub fn verify_signature<E: EthSpec>(
+        &self,
+        bid: &SignedBuilderBid<E>,
+        relay_pubkey: &PublicKey,
+    ) -> Result<(), BidError> {
+        let message = bid.message();
+        let expected = relay_pubkey.compress();
+        if *message.pubkey() != expected {
+            return Err(BidError::UnexpectedPubkey {
+                expected,
+                got: *message.pubkey(),
+            });
+        }
+        if !bid
+            .signature()
+            .verify(relay_pubkey, message.signing_root(self.builder_domain))
+        {
+            return Err(BidError::InvalidSignature);
+        }
+        Ok(())
+    }
+
+    /// Check that `bid` is a valid answer from the relay with `relay_pubkey` to a get header
+    /// request for `params`. Bids do not name the proposer, so only its slot and parent hash are
+    /// checked against the request.
+    pub fn verify<E: EthSpec>(
+        &self,
+        bid: &SignedBuilderBid<E>,
+        params: &GetHeaderParams,
+        relay_pubkey: &PublicKey,
+    ) -> Result<(), BidError> {
+        let message = bid.message();
+        let header = message.header();
+
+        if header.parent_hash() != params.parent_hash {
+            return Err(BidError::WrongParentHash {
+                expected: params.parent_hash,
+                got: header.parent_hash(),
+            });
+        }
+        if let Some(genesis_time) = self.genesis_time {
+            let expected = genesis_time + params.slot.as_u64() * self.seconds_per_slot;
+            if header.timestamp() != expected {
+                return Err(BidError::WrongTimestamp {
+                    expected,
+                    got: header.timestamp(),
+                });
+            }
+        }
+        if *message.value() == Uint256::zero() {
+            return Err(BidError::ZeroValue);
+        }
+
+        self.verify_signature(bid, relay_pubkey)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use types::{ExecutionPayloadHeaderCapella, Keypair, MainnetEthSpec, Slot};
+
+    use super::*;
+    use crate::{BuilderBid, BuilderBidCapella};
+
+    type E = MainnetEthSpec;
+
+    fn bid(keypair: &Keypair, spec: &ChainSpec, params: &GetHeaderParams) -> SignedBuilderBid<E> {
+        BuilderBid::Capella(BuilderBidCapella {
+            header: ExecutionPayloadHeaderCapella {
+                parent_hash: params.parent_hash,
+                timestamp: params.slot.as_u64() * spec.seconds_per_slot,
+                ..Default::default()
+            },
+            value: Uint256::from(1),
+            pubkey: keypair.pk.compress(),
+        })
+        .sign(&keypair.sk, spec)
+    }
+
+    #[test]
+    fn verify_bid() {
+        let spec = E::default_spec();
+        let verifier = BidVerifier::new(&spec).with_genesis_time(0);
+        let keypair = Keypair::random();
+        let params = GetHeaderParams {
+            slot: Slot::new(10),
+            parent_hash: ExecutionBlockHash::from_root(Hash256::repeat_byte(1)),
+            pubkey: PublicKeyBytes::empty(),
+        };
+        let bid = bid(&keypair, &spec, &params);
+
+        assert_eq!(verifier.verify(&bid, &params, &keypair.pk), Ok(()));
+        assert!(matches!(
+            verifier.verify(&bid, &params, &Keypair::random().pk),
+            Err(BidError::UnexpectedPubkey { .. })
+        ));
+
+        let mut tampered = bid.clone();
+        if let SignedBuilderBid::Capella(tampered) = &mut tampered {
+            tampered.message.value = Uint256::from(2);
+        }
+        assert_eq!(
+            verifier.verify(&tampered, &params, &keypair.pk),
+            Err(BidError::InvalidSignature)
+        );
+
+        let other_slot = GetHeaderParams {
+            slot: Slot::new(11),
+            ..params.clone()
+        };
+        assert_eq!(
+            verifier.verify(&bid, &other_slot, &keypair.pk),
+            Err(BidError::WrongTimestamp {
+                expected: 11 * spec.seconds_per_slot,
+                got: 10 * spec.seconds_per_slot,
+            })
+        );
+
+        let other_parent = GetHeaderParams {
+            parent_hash: ExecutionBlockHash::zero(),
+            ..params
+        };
+        assert!(matches!(
+            verifier.verify(&bid, &other_parent, &keypair.pk),
+            Err(BidError::WrongParentHash { .. })
+        ));
+
+        let mut message = bid.as_capella().unwrap().message.clone();
+        message.value = Uint256::zero();
+        let zero_value = BuilderBid::Capella(message).sign(&keypair.sk, &spec);
+        assert_eq!(
+            verifier.verify(&zero_value, &params, &keypair.pk),
+            Err(BidError::ZeroValue)
+        );
+        assert_eq!(BidError::ZeroValue.to_string(), "bid has zero value");
+    }
+}
@@ -0,0 +1,360 @@
+use std::str::FromStr;
+
+use builder_api_types::{
+    blinded_block_fork_name, registration::registrations_as_ssz_bytes, ForkVersionDecode,
+    GetHeaderResponse, SignedBlindedBeaconBlock, SubmitBlindedBlockResponse, VersionedResponse,
+    CONSENSUS_VERSION_HEADER,
+};
+use http::header::{ACCEPT, CONTENT_TYPE};
+use reqwest::{Client, StatusCode};
+use ssz::Encode;
+use types::{
+    eth_spec::EthSpec, ExecutionBlockHash, ForkName, PublicKeyBytes,
+    SignedValidatorRegistrationData, Slot,
+};
+
+const JSON_CONTENT_TYPE: &str = "application/json";
+const SSZ_CONTENT_TYPE: &str = "application/octet-stream";
+
+#[derive(Debug)]
+pub enum Error {
+    Reqwest(reqwest::Error),
+    InvalidJson(serde_json::Error, String),
+    InvalidSsz(ssz::DecodeError),
+    /// An SSZ response without a valid `Eth-Consensus-Version` header.
+    InvalidConsensusVersion(Option<String>),
+    ServerMessage(String),
+    StatusCode(http::StatusCode),
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Reqwest(e)
+    }
+}
+
+impl From<ssz::DecodeError> for Error {
+    fn from(e: ssz::DecodeError) -> Self {
+        Error::InvalidSsz(e)
+    }
+}
+
+/// Encoding of request bodies, and the preferred encoding of responses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Encoding {
+    #[default]
+    Json,
+    Ssz,
+}
+
+impl Encoding {
+    fn content_type(&self) -> &'static str {
+        match self {
+            Encoding::Json => JSON_CONTENT_TYPE,
+            Encoding::Ssz => SSZ_CONTENT_TYPE,
+        }
+    }
+
+    /// Builders are not required to support SSZ, so JSON is always accepted as a fallback.
+    fn accept(&self) -> &'static str {
+        match self {
+            Encoding::Json => JSON_CONTENT_TYPE,
+            Encoding::Ssz => "application/octet-stream;q=1.0,application/json;q=0.9",
+        }
+    }
+}
+
+pub struct BuilderClient {
+    client: Client,
+    base_url: String,
+    encoding: Encoding,
+}
+
+impl BuilderClient {
+    pub fn new(base_url: String) -> Self {
+        Self {
+            client: Client::new(),
+            base_url,
+            encoding: Encoding::default(),
+        }
+    }
+
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    async fn error(&self, response: reqwest::Response) -> Error {
+        let status = response.status();
+        match response.text().await {
+            Ok(message) => Error::ServerMessage(message),
+            Err(_) => Error::StatusCode(status),
+        }
+    }
+
+    async fn build_empty_response(&self, response: reqwest::Response) -> Result<(), Error> {
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(self.error(response).await)
+        }
+    }
+
+    /// Decode a fork-versioned response from JSON or, depending on its content type, from SSZ
+    /// with the fork taken from the `Eth-Consensus-Version` header.
+    async fn build_versioned_response<T>(
+        &self,
+        response: reqwest::Response,
+    ) -> Result<VersionedResponse<T>, Error>
+    where
+        T: ForkVersionDecode,
+    {
+        if !response.status().is_success() {
+            return Err(self.error(response).await);
+        }
+
+        let is_ssz = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .is_some_and(|content_type| content_type.starts_with(SSZ_CONTENT_TYPE));
+        if !is_ssz {
+            let text = response.text().await?;
+            return serde_json::from_str(&text).map_err(|e| Error::InvalidJson(e, text));
+        }
+
+        let version = response
+            .headers()
+            .get(CONSENSUS_VERSION_HEADER)
+            .map(|version| version.to_str().unwrap_or_default().to_owned());
+        let Some(fork_name) = version
+            .as_deref()
+            .and_then(|version| ForkName::from_str(version).ok())
+        else {
+            return Err(Error::InvalidConsensusVersion(version));
+        };
+        let bytes = response.bytes().await?;
+        Ok(VersionedResponse {
+            version: fork_name,
+            data: T::from_ssz_bytes_by_fork(&bytes, fork_name)?,
+        })
+    }
+
+    fn post_blinded_block<E>(
+        &self,
+        url: &str,
+        block: &SignedBlindedBeaconBlock<E>,
+    ) -> reqwest::RequestBuilder
+    where
+        E: EthSpec,
+    {
+        let request = self
+            .client
+            .post(url)
+            .header(
+                CONSENSUS_VERSION_HEADER,
+                blinded_block_fork_name(block).to_string(),
+            )
+            .header(ACCEPT, self.encoding.accept());
+        match self.encoding {
+            Encoding::Json => request.json(block),
+            Encoding::Ssz => request
+                .header(CONTENT_TYPE, self.encoding.content_type())
+                .body(block.as_ssz_bytes()),
+        }
+    }
+
+    pub async fn register_validators(
+        &self,
+        registrations: &[SignedValidatorRegistrationData],
+    ) -> Result<(), Error> {
+        let url = format!("{}/eth/v1/builder/validators", self.base_url);
+        let request = self.client.post(&url);
+        let request = match self.encoding {
+            Encoding::Json => request.json(registrations),
+            Encoding::Ssz => request
+                .header(CONTENT_TYPE, self.encoding.content_type())
+                .body(registrations_as_ssz_bytes(registrations)),
+        };
+        let response = request.send().await?;
+
+        self.build_empty_response(response).await
+    }
+
+    /// Returns `None` if the builder has no bid for the slot.
+    pub async fn get_header<E>(
+        &self,
+        slot: Slot,
+        parent_hash: ExecutionBlockHash,
+        pubkey: PublicKeyBytes,
+    ) -> Result<Option<GetHeaderResponse<E>>, Error>
+    where
+        E: EthSpec,
+    {
+        let url = format!(
+            "{}/eth/v1/builder/header/{slot}/{parent_hash:?}/{pubkey:?}",
+            self.base_url
+        );
+        let response = self
+            .client
+            .get(&url)
+            .header(ACCEPT, self.encoding.accept())
+            .send()
+            .await?;
+
+        if response.status() == StatusCode::NO_CONTENT {
+            return Ok(None);
+        }
+        self.build_versioned_response(response).await.map(Some)
+    }
+
+    pub async fn submit_blinded_block<E>(
+        &self,
+        block: &SignedBlindedBeaconBlock<E>,
+    ) -> Result<SubmitBlindedBlockResponse<E>, Error>
+    where
+        E: EthSpec,
+    {
+        let url = format!("{}/eth/v1/builder/blinded_blocks", self.base_url);
+        let response = self.post_blinded_block(&url, block).send().await?;
+
+        self.build_versioned_response(response).await
+    }
+
+    /// Submit a blinded block without getting the payload back. The builder publishes the block
+    /// itself and answers with `202 Accepted`.
+    pub async fn submit_blinded_block_v2<E>(
+        &self,
+        block: &SignedBlindedBeaconBlock<E>,
+    ) -> Result<(), Error>
+    where
+        E: EthSpec,
+    {
+        let url = format!("{}/eth/v2/builder/blinded_blocks", self.base_url);
+        let response = self.post_blinded_block(&url, block).send().await?;
+
+        self.build_empty_response(response).await
+    }
+
+    pub async fn status(&self) -> Result<(), Error> {
+        let url = format!("{}/eth/v1/builder/status", self.base_url);
+        let response = self.client.get(&url).send().await?;
+
+        self.build_empty_response(response).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{http::HeaderMap, response::IntoResponse, routing::get, Json, Router};
+    use builder_api_types::{BuilderBid, BuilderBidCapella, SignedBuilderBid};
+    use types::{ChainSpec, Keypair, MainnetEthSpec, Uint256};
+
+    use super::*;
+
+    type E = MainnetEthSpec;
+
+    const HEADER_PATH: &str = "/eth/v1/builder/header/:slot/:parent_hash/:pubkey";
+
+    /// Serve `router` on a local port and return a client for it.
+    async fn serve(router: Router) -> BuilderClient {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        BuilderClient::new(url)
+    }
+
+    fn bid() -> GetHeaderResponse<E> {
+        let bid = BuilderBid::Capella(BuilderBidCapella {
+            header: Default::default(),
+            value: Uint256::from(100),
+            pubkey: PublicKeyBytes::empty(),
+        });
+        VersionedResponse {
+            version: ForkName::Capella,
+            data: bid.sign(&Keypair::random().sk, &ChainSpec::mainnet()),
+        }
+    }
+
+    async fn get_header(client: &BuilderClient) -> Result<Option<GetHeaderResponse<E>>, Error> {
+        client
+            .get_header::<E>(
+                Slot::new(1),
+                ExecutionBlockHash::zero(),
+                PublicKeyBytes::empty(),
+            )
+            .await
+    }
+
+    #[tokio::test]
+    async fn get_header_encodings() {
+        let expected = bid();
+        let response = expected.clone();
+        let router = Router::new().route(
+            HEADER_PATH,
+            get(move |headers: HeaderMap| async move {
+                let accepts_ssz = headers
+                    .get(ACCEPT)
+                    .and_then(|accept| accept.to_str().ok())
+                    .is_some_and(|accept| accept.starts_with(SSZ_CONTENT_TYPE));
+                if accepts_ssz {
+                    let headers = [
+                        (CONTENT_TYPE, SSZ_CONTENT_TYPE.to_owned()),
+                        (
+                            CONSENSUS_VERSION_HEADER.parse().unwrap(),
+                            response.version.to_string(),
+                        ),
+                    ];
+                    (headers, response.data.as_ssz_bytes()).into_response()
+                } else {
+                    Json(response).into_response()
+                }
+            }),
+        );
+        let client = serve(router).await;
+
+        assert_eq!(get_header(&client).await.unwrap(), Some(expected.clone()));
+        let client = client.with_encoding(Encoding::Ssz);
+        assert_eq!(get_header(&client).await.unwrap(), Some(expected));
+    }
+
+    #[tokio::test]
+    async fn get_header_without_bid() {
+        let router = Router::new().route(HEADER_PATH, get(|| async { StatusCode::NO_CONTENT }));
+        let client = serve(router).await;
+        assert_eq!(get_header(&client).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn ssz_without_consensus_version() {
+        let router = Router::new().route(
+            HEADER_PATH,
+            get(|| async {
+                let data: SignedBuilderBid<E> = bid().data;
+                ([(CONTENT_TYPE, SSZ_CONTENT_TYPE)], data.as_ssz_bytes()).into_response()
+            }),
+        );
+        let client = serve(router).await.with_encoding(Encoding::Ssz);
+        assert!(matches!(
+            get_header(&client).await,
+            Err(Error::InvalidConsensusVersion(None))
+        ));
+    }
+
+    #[tokio::test]
+    async fn server_message() {
+        let router = Router::new().route(
+            "/eth/v1/builder/status",
+            get(|| async { (StatusCode::SERVICE_UNAVAILABLE, "no relay available") }),
+        );
+        let client = serve(router).await;
+        assert!(matches!(
+            client.status().await,
+            Err(Error::ServerMessage(message)) if message == "no relay available"
+        ));
+    }
+}
@@ -0,0 +1,352 @@
+use std::{
+    fmt, fs, io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use relay_server::config::Network;
+use serde::Deserialize;
+use types::{PublicKeyBytes, Uint256};
+
+use crate::{
+    health::{
+        HealthPolicy, DEFAULT_MIN_SAMPLES, DEFAULT_MIN_SUCCESS_RATE, DEFAULT_STATUS_INTERVAL,
+    },
+    multiplexer::{
+        DEFAULT_GET_HEADER_TIMEOUT, DEFAULT_REGISTER_VALIDATORS_TIMEOUT, DEFAULT_STATUS_TIMEOUT,
+        DEFAULT_SUBMIT_BLINDED_BLOCK_TIMEOUT,
+    },
+};
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Yaml(serde_yaml::Error),
+    UnknownFormat(PathBuf),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "unable to read config: {e}"),
+            Error::Toml(e) => write!(f, "invalid TOML config: {e}"),
+            Error::Yaml(e) => write!(f, "invalid YAML config: {e}"),
+            Error::UnknownFormat(path) => write!(
+                f,
+                "unknown config format of {}, expected .toml, .yaml or .yml",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for Error {
+    fn from(e: toml::de::Error) -> Self {
+        Error::Toml(e)
+    }
+}
+
+impl From<serde_yaml::Error> for Error {
+    fn from(e: serde_yaml::Error) -> Self {
+        Error::Yaml(e)
+    }
+}
+
+/// Configuration of a multiplexer process.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub listen_address: SocketAddr,
+    #[serde(default)]
+    pub network: Network,
+    /// Genesis time in seconds since the Unix epoch, used to check bid timestamps. Defaults to
+    /// the network preset's genesis time, if it has one.
+    #[serde(default)]
+    pub genesis_time: Option<u64>,
+    pub relays: Vec<RelayConfig>,
+    /// Bids worth less than this many wei are ignored.
+    #[serde(default, with = "serde_utils::quoted_u256")]
+    pub min_bid_wei: Uint256,
+    #[serde(default)]
+    pub timeouts: Timeouts,
+    #[serde(default)]
+    pub health: HealthConfig,
+}
+
+impl Config {
+    pub fn genesis_time(&self) -> Option<u64> {
+        self.genesis_time.or(self.network.genesis_time())
+    }
+
+    /// Load a config file, picking the format from the `.toml`, `.yaml` or `.yml` extension.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path)?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Ok(toml::from_str(&contents)?),
+            Some("yaml" | "yml") => Ok(serde_yaml::from_str(&contents)?),
+            _ => Err(Error::UnknownFormat(path.to_path_buf())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RelayConfig {
+    /// Base URL of the relay, e.g. `https://relay.example.org`.
+    pub url: String,
+    /// Key the relay signs its bids with.
+    pub pubkey: PublicKeyBytes,
+    /// Overrides `timeouts.get_header_ms` for this relay.
+    #[serde(default)]
+    pub get_header_timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Timeouts {
+    /// How long to wait for each relay's bid. Relays answering later are ignored.
+    pub get_header_ms: u64,
+    pub submit_blinded_block_ms: u64,
+    pub register_validators_ms: u64,
+    pub status_ms: u64,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            get_header_ms: DEFAULT_GET_HEADER_TIMEOUT.as_millis() as u64,
+            submit_blinded_block_ms: DEFAULT_SUBMIT_BLINDED_BLOCK_TIMEOUT.as_millis() as u64,
+            register_validators_ms: DEFAULT_REGISTER_VALIDATORS_TIMEOUT.as_millis() as u64,
+            status_ms: DEFAULT_STATUS_TIMEOUT.as_millis() as u64,
+        }
+    }
+}
+
+impl Timeouts {
+    pub fn get_header(&self) -> Duration {
+        Duration::from_millis(self.get_header_ms)
+    }
+
+    pub fn submit_blinded_block(&self) -> Duration {
+        Duration::from_millis(self.submit_blinded_block_ms)
+    }
+
+    pub fn register_validators(&self) -> Duration {
+        Duration::from_millis(self.register_validators_ms)
+    }
+
+    pub fn status(&self) -> Duration {
+        Duration::from_millis(self.status_ms)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HealthConfig {
+    /// How often every relay's status is checked. Relays failing the check are not asked for
+    /// headers until they pass it again.
+    pub status_interval_ms: u64,
+    /// Number of recent get header requests needed before a relay can be judged unhealthy.
+    pub min_samples: usize,
+    /// Share of recent get header requests a relay must answer in time with a valid response.
+    /// Bids of relays below it only win if no healthy relay bids.
+    pub min_success_rate: f64,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            status_interval_ms: DEFAULT_STATUS_INTERVAL.as_millis() as u64,
+            min_samples: DEFAULT_MIN_SAMPLES,
+            min_success_rate: DEFAULT_MIN_SUCCESS_RATE,
+        }
+    }
+}
+
+impl HealthConfig {
+    pub fn status_interval(&self) -> Duration {
+        Duration::from_millis(self.status_interval_ms)
+    }
+
+    pub fn policy(&self) -> HealthPolicy {
+        HealthPolicy {
+            min_samples: self.min_samples,
+            min_success_rate: self.min_success_rate,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUBKEY: &str = "0xabababababababababababababababababababababababababababababababababababababababababababababababab";
+
+    fn toml() -> String {
+        format!(
+            r#"
+listen_address = "0.0.0.0:18550"
+network = "minimal"
+genesis_time = 100
+min_bid_wei = "1000"
+
+[[relays]]
+url = "https://a.example.org"
+pubkey = "{PUBKEY}"
+
+[[relays]]
+url = "https://b.example.org"
+pubkey = "{PUBKEY}"
+get_header_timeout_ms = 500
+
+[timeouts]
+get_header_ms = 900
+
+[health]
+min_success_rate = 0.5
+"#
+        )
+    }
+
+    fn yaml() -> String {
+        format!(
+            r#"
+listen_address: 0.0.0.0:18550
+network: minimal
+genesis_time: 100
+min_bid_wei: "1000"
+relays:
+  - url: https://a.example.org
+    pubkey: "{PUBKEY}"
+  - url: https://b.example.org
+    pubkey: "{PUBKEY}"
+    get_header_timeout_ms: 500
+timeouts:
+  get_header_ms: 900
+health:
+  min_success_rate: 0.5
+"#
+        )
+    }
+
+    /// A fresh directory under the system's temporary directory.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("multiplexer-config-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn defaults() {
+        let config: Config =
+            toml::from_str("listen_address = \"127.0.0.1:18550\"\nrelays = []").unwrap();
+        assert_eq!(config.network, Network::Mainnet);
+        assert_eq!(config.genesis_time(), Some(1606824023));
+        assert!(config.relays.is_empty());
+        assert_eq!(config.min_bid_wei, Uint256::zero());
+        assert_eq!(config.timeouts, Timeouts::default());
+        assert_eq!(config.timeouts.get_header(), DEFAULT_GET_HEADER_TIMEOUT);
+        assert_eq!(config.health, HealthConfig::default());
+        assert_eq!(config.health.status_interval(), DEFAULT_STATUS_INTERVAL);
+    }
+
+    #[test]
+    fn toml_and_yaml() {
+        let config: Config = toml::from_str(&toml()).unwrap();
+        assert_eq!(serde_yaml::from_str::<Config>(&yaml()).unwrap(), config);
+
+        assert_eq!(config.network, Network::Minimal);
+        assert_eq!(config.genesis_time(), Some(100));
+        assert_eq!(config.min_bid_wei, Uint256::from(1000));
+        assert_eq!(
+            config.relays[0],
+            RelayConfig {
+                url: "https://a.example.org".into(),
+                pubkey: PUBKEY.parse().unwrap(),
+                get_header_timeout_ms: None,
+            }
+        );
+        assert_eq!(config.relays[1].get_header_timeout_ms, Some(500));
+        // Unset fields of a section keep their defaults.
+        assert_eq!(
+            config.timeouts,
+            Timeouts {
+                get_header_ms: 900,
+                ..Timeouts::default()
+            }
+        );
+        assert_eq!(
+            config.health.policy(),
+            HealthPolicy {
+                min_samples: DEFAULT_MIN_SAMPLES,
+                min_success_rate: 0.5,
+            }
+        );
+    }
+
+    #[test]
+    fn unknown_fields_rejected() {
+        for toml in [
+            "listen_address = \"127.0.0.1:18550\"",
+            "listen_address = \"127.0.0.1:18550\"\nrelays = []\nmin_bid = \"1\"",
+            "listen_address = \"127.0.0.1:18550\"\nrelays = []\n[timeouts]\nget_header = 1",
+            "listen_address = \"127.0.0.1:18550\"\nrelays = []\n[health]\nmin_rate = 0.5",
+        ] {
+            assert!(toml::from_str::<Config>(toml).is_err(), "{toml}");
+        }
+        let yaml = format!(
+            "listen_address: 127.0.0.1:18550\nrelays:\n  - url: https://a.example.org\n    pubkey: \"{PUBKEY}\"\n    timeout_ms: 1"
+        );
+        assert!(serde_yaml::from_str::<Config>(&yaml).is_err());
+    }
+
+    #[test]
+    fn load() {
+        let dir = temp_dir("load");
+        for (name, contents) in [
+            ("multiplexer.toml", toml()),
+            ("multiplexer.yaml", yaml()),
+            ("multiplexer.yml", yaml()),
+        ] {
+            fs::write(dir.join(name), contents).unwrap();
+            assert_eq!(
+                Config::load(&dir.join(name)).unwrap(),
+                toml::from_str(&toml()).unwrap()
+            );
+        }
+
+        let path = dir.join("multiplexer.json");
+        fs::write(&path, "{}").unwrap();
+        let e = Config::load(&path).unwrap_err();
+        assert!(matches!(&e, Error::UnknownFormat(unknown) if *unknown == path));
+        assert_eq!(
+            e.to_string(),
+            format!(
+                "unknown config format of {}, expected .toml, .yaml or .yml",
+                path.display()
+            )
+        );
+        assert!(matches!(
+            Config::load(&dir.join("missing.yaml")),
+            Err(Error::Io(_))
+        ));
+        fs::write(dir.join("invalid.yaml"), "listen_address: [").unwrap();
+        assert!(Config::load(&dir.join("invalid.yaml"))
+            .unwrap_err()
+            .to_string()
+            .starts_with("invalid YAML config"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
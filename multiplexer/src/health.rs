@@ -0,0 +1,176 @@
+use std::{collections::VecDeque, time::Duration};
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+/// Number of recent get header requests the health of a relay is judged on.
+pub const HEALTH_WINDOW: usize = 100;
+/// Default number of recent requests needed before a relay can be judged unhealthy.
+pub const DEFAULT_MIN_SAMPLES: usize = 10;
+/// Default share of recent get header requests a healthy relay answers in time.
+pub const DEFAULT_MIN_SUCCESS_RATE: f64 = 0.5;
+/// Default time between status checks of every relay.
+pub const DEFAULT_STATUS_INTERVAL: Duration = Duration::from_secs(12);
+
+/// How a get header request to a relay ended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    /// The relay answered in time with a valid bid or no bid.
+    Success(Duration),
+    /// The relay answered with an error or an invalid bid.
+    Error,
+    Timeout,
+}
+
+/// Recent get header outcomes and the last status check of a relay.
+#[derive(Debug, Default)]
+pub struct RelayHealth {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    outcomes: VecDeque<Outcome>,
+    status_ok: Option<bool>,
+}
+
+impl RelayHealth {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the outcome of a get header request, forgetting the oldest beyond
+    /// [`HEALTH_WINDOW`].
+    pub fn record(&self, outcome: Outcome) {
+        let mut inner = self.inner.lock();
+        if inner.outcomes.len() == HEALTH_WINDOW {
+            inner.outcomes.pop_front();
+        }
+        inner.outcomes.push_back(outcome);
+    }
+
+    pub fn record_status(&self, ok: bool) {
+        self.inner.lock().status_ok = Some(ok);
+    }
+
+    pub fn stats(&self) -> HealthStats {
+        let inner = self.inner.lock();
+        let mut latencies = Vec::with_capacity(inner.outcomes.len());
+        let (mut errors, mut timeouts) = (0, 0);
+        for outcome in &inner.outcomes {
+            match outcome {
+                Outcome::Success(latency) => latencies.push(*latency),
+                Outcome::Error => errors += 1,
+                Outcome::Timeout => timeouts += 1,
+            }
+        }
+        latencies.sort_unstable();
+
+        let requests = inner.outcomes.len();
+        HealthStats {
+            status_ok: inner.status_ok,
+            requests,
+            errors,
+            timeouts,
+            success_rate: if requests == 0 {
+                1.0
+            } else {
+                latencies.len() as f64 / requests as f64
+            },
+            latency_p50_ms: percentile_ms(&latencies, 50),
+            latency_p90_ms: percentile_ms(&latencies, 90),
+            latency_p99_ms: percentile_ms(&latencies, 99),
+        }
+    }
+}
+
+/// Nearest-rank percentile of `sorted` latencies, in milliseconds.
+fn percentile_ms(sorted: &[Duration], percentile: usize) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (sorted.len() * percentile).div_ceil(100).max(1);
+    Some(sorted[rank - 1].as_millis() as u64)
+}
+
+/// Health of a relay over its recent get header requests.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HealthStats {
+    /// Result of the last status check, `None` before the first one.
+    pub status_ok: Option<bool>,
+    pub requests: usize,
+    pub errors: usize,
+    pub timeouts: usize,
+    /// Share of recent requests answered in time with a valid response, 1 without requests.
+    pub success_rate: f64,
+    pub latency_p50_ms: Option<u64>,
+    pub latency_p90_ms: Option<u64>,
+    pub latency_p99_ms: Option<u64>,
+}
+
+/// Judges relays by their [`HealthStats`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HealthPolicy {
+    pub min_samples: usize,
+    pub min_success_rate: f64,
+}
+
+impl Default for HealthPolicy {
+    fn default() -> Self {
+        Self {
+            min_samples: DEFAULT_MIN_SAMPLES,
+            min_success_rate: DEFAULT_MIN_SUCCESS_RATE,
+        }
+    }
+}
+
+impl HealthPolicy {
+    /// Score between 0 and 1, 0 for relays failing their status check.
+    pub fn score(&self, stats: &HealthStats) -> f64 {
+        if stats.status_ok == Some(false) {
+            0.0
+        } else {
+            stats.success_rate
+        }
+    }
+
+    /// Whether the relay passed its last status check and, once it has enough recent requests,
+    /// answers enough of them.
+    pub fn is_healthy(&self, stats: &HealthStats) -> bool {
+        stats.status_ok != Some(false)
+            && (stats.requests < self.min_samples || stats.success_rate >= self.min_success_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_and_policy() {
+        let health = RelayHealth::new();
+        let policy = HealthPolicy::default();
+        assert!(policy.is_healthy(&health.stats()));
+
+        for ms in 1..=8 {
+            health.record(Outcome::Success(Duration::from_millis(ms * 100)));
+        }
+        health.record(Outcome::Error);
+        health.record(Outcome::Timeout);
+
+        let stats = health.stats();
+        assert_eq!((stats.requests, stats.errors, stats.timeouts), (10, 1, 1));
+        assert_eq!(stats.success_rate, 0.8);
+        assert_eq!(stats.latency_p50_ms, Some(400));
+        assert_eq!(stats.latency_p90_ms, Some(800));
+        assert!(policy.is_healthy(&stats));
+
+        for _ in 0..10 {
+            health.record(Outcome::Timeout);
+        }
+        assert!(!policy.is_healthy(&health.stats()));
+
+        health.record_status(false);
+        assert_eq!(policy.score(&health.stats()), 0.0);
+    }
+}
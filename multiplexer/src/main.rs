@@ -0,0 +1,188 @@
+use std::{io, path::PathBuf, process::ExitCode, sync::Arc, time::Duration};
+
+use axum::{routing::get, Json, Router};
+use axum_server::Handle;
+use multiplexer::{
+    config::Config,
+    multiplexer::{Multiplexer, Relay},
+};
+use relay_server::{config::Network, server::RouterBuilder};
+use tokio::signal;
+use tracing::{error, info};
+use types::{EthSpec, GnosisEthSpec, MainnetEthSpec, MinimalEthSpec};
+
+fn main() -> ExitCode {
+    tracing_subscriber::fmt::init();
+
+    let Some(config_path) = std::env::args_os().nth(1).map(PathBuf::from) else {
+        eprintln!("Usage: multiplexer <config.toml|config.yaml>");
+        return ExitCode::FAILURE;
+    };
+
+    let config = match Config::load(&config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            error!(error = ?e, path = ?config_path, "Failed to load config");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let runtime = match tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            error!(error = ?e, "Failed to start runtime");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match runtime.block_on(run(config)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!(error = ?e, "Multiplexer failed");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(config: Config) -> io::Result<()> {
+    let router = match config.network {
+        Network::Mainnet => router::<MainnetEthSpec>(&config),
+        Network::Gnosis => router::<GnosisEthSpec>(&config),
+        Network::Minimal => router::<MinimalEthSpec>(&config),
+    }?;
+
+    let handle = Handle::new();
+    tokio::spawn(shutdown_on_signal(handle.clone()));
+
+    info!(
+        address = %config.listen_address,
+        relays = config.relays.len(),
+        "Starting multiplexer"
+    );
+    axum_server::bind(config.listen_address)
+        .handle(handle)
+        .serve(router.into_make_service())
+        .await
+}
+
+fn router<E: EthSpec>(config: &Config) -> io::Result<Router> {
+    let relays = config
+        .relays
+        .iter()
+        .map(|relay| {
+            let pubkey = relay.pubkey.decompress().map_err(|e| {
+                io::Error::other(format!("invalid pubkey for relay {}: {e:?}", relay.url))
+            })?;
+            let timeout = relay
+                .get_header_timeout_ms
+                .map(Duration::from_millis)
+                .unwrap_or(config.timeouts.get_header());
+            Ok(Relay::new(relay.url.clone(), pubkey).with_get_header_timeout(timeout))
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    let mut multiplexer = Multiplexer::<E>::new(relays, &config.network.chain_spec())
+        .with_min_bid(config.min_bid_wei)
+        .with_timeouts(config.timeouts.clone())
+        .with_health_policy(config.health.policy());
+    if let Some(genesis_time) = config.genesis_time() {
+        multiplexer = multiplexer.with_genesis_time(genesis_time);
+    }
+    let multiplexer = Arc::new(multiplexer);
+    tokio::spawn(
+        multiplexer
+            .clone()
+            .check_status_every(config.health.status_interval()),
+    );
+
+    let stats = multiplexer.clone();
+    Ok(RouterBuilder::new()
+        .proposer_api::<_, Multiplexer<E>, E>(multiplexer)
+        .route(
+            "/relays",
+            get(move || async move { Json(stats.relay_stats()) }),
+        )
+        .build())
+}
+
+/// Stop accepting connections on SIGTERM or SIGINT.
+async fn shutdown_on_signal(handle: Handle) {
+    let interrupt = async {
+        if let Err(e) = signal::ctrl_c().await {
+            error!(error = ?e, "Failed to listen for SIGINT");
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                error!(error = ?e, "Failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => {},
+        _ = terminate => {},
+    }
+
+    info!("Shutting down");
+    handle.shutdown();
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::{self, Body},
+        http::{Request, StatusCode},
+    };
+    use multiplexer::config::{HealthConfig, RelayConfig, Timeouts};
+    use tower::ServiceExt;
+    use types::{Keypair, Uint256};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn relay_stats_route() {
+        let config = Config {
+            listen_address: "127.0.0.1:0".parse().unwrap(),
+            network: Network::Mainnet,
+            genesis_time: None,
+            relays: vec![RelayConfig {
+                url: "http://127.0.0.1:1".to_owned(),
+                pubkey: Keypair::random().pk.compress(),
+                get_header_timeout_ms: None,
+            }],
+            min_bid_wei: Uint256::zero(),
+            timeouts: Timeouts::default(),
+            health: HealthConfig::default(),
+        };
+        let router = router::<MainnetEthSpec>(&config).unwrap();
+
+        let request = Request::get("/relays").body(Body::empty()).unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let stats: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let stats = stats.as_array().unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0]["url"], "http://127.0.0.1:1");
+        assert_eq!(stats[0]["requests"], 0);
+        for field in ["healthy", "score", "success_rate", "latency_p50_ms"] {
+            assert!(stats[0].get(field).is_some(), "missing {field}");
+        }
+    }
+}
@@ -0,0 +1,731 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    marker::PhantomData,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use builder_api_types::{
+    unblind::unblind_block, verify::BidVerifier, GetHeaderParams, GetHeaderResponse,
+    SignedBlindedBeaconBlock, SubmitBlindedBlockResponse,
+};
+use builder_client::BuilderClient;
+use parking_lot::Mutex;
+use relay_server::{proposer::Proposer, ErrorResponse, Response};
+use serde::Serialize;
+use tokio::task::JoinSet;
+use tracing::{debug, warn};
+use types::{
+    eth_spec::EthSpec, ChainSpec, ExecPayload, ExecutionBlockHash, PublicKey,
+    SignedValidatorRegistrationData, Slot, Uint256,
+};
+
+use crate::{
+    config::Timeouts,
+    health::{HealthPolicy, HealthStats, Outcome, RelayHealth},
+};
+
+/// Default time each relay has to return a bid.
+pub const DEFAULT_GET_HEADER_TIMEOUT: Duration = Duration::from_millis(950);
+/// Default time relays have to return the payload of a signed blinded block.
+pub const DEFAULT_SUBMIT_BLINDED_BLOCK_TIMEOUT: Duration = Duration::from_secs(4);
+/// Default time relays have to accept validator registrations.
+pub const DEFAULT_REGISTER_VALIDATORS_TIMEOUT: Duration = Duration::from_secs(3);
+/// Default time relays have to answer a status check.
+pub const DEFAULT_STATUS_TIMEOUT: Duration = Duration::from_secs(1);
+/// Number of slots the relays that returned a header are remembered for.
+pub const BID_RETENTION_SLOTS: u64 = 64;
+
+/// A relay bids are requested from.
+pub struct Relay {
+    pub client: BuilderClient,
+    /// Key the relay signs its bids with.
+    pub pubkey: PublicKey,
+    pub get_header_timeout: Duration,
+    pub health: RelayHealth,
+}
+
+impl Relay {
+    pub fn new(url: String, pubkey: PublicKey) -> Self {
+        Self {
+            client: BuilderClient::new(url),
+            pubkey,
+            get_header_timeout: DEFAULT_GET_HEADER_TIMEOUT,
+            health: RelayHealth::new(),
+        }
+    }
+
+    pub fn with_get_header_timeout(mut self, timeout: Duration) -> Self {
+        self.get_header_timeout = timeout;
+        self
+    }
+
+    fn url(&self) -> &str {
+        self.client.base_url()
+    }
+}
+
+/// Health of a relay, as served to dashboards.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RelayStats {
+    pub url: String,
+    pub healthy: bool,
+    pub score: f64,
+    #[serde(flatten)]
+    pub health: HealthStats,
+}
+
+/// Serves the Builder API to a proposer from several relays: headers are requested from every
+/// relay and the highest valid bid wins, blinded blocks go back to the relays that returned the
+/// header.
+///
+/// Relays failing their status check are not asked for headers. Bids of relays failing too many
+/// recent get header requests only win if no healthy relay bids.
+pub struct Multiplexer<E: EthSpec> {
+    relays: Vec<Arc<Relay>>,
+    verifier: BidVerifier,
+    min_bid: Uint256,
+    timeouts: Timeouts,
+    health_policy: HealthPolicy,
+    /// Indices of the relays that returned each header, by slot and block hash.
+    bids: Mutex<BTreeMap<Slot, HashMap<ExecutionBlockHash, Vec<usize>>>>,
+    _phantom: PhantomData<E>,
+}
+
+fn error<T>(code: u16, message: impl Into<String>) -> Response<T> {
+    Response::Error(ErrorResponse {
+        code,
+        message: message.into(),
+        stacktraces: None,
+    })
+}
+
+impl<E: EthSpec> Multiplexer<E> {
+    pub fn new(relays: Vec<Relay>, spec: &ChainSpec) -> Self {
+        Self {
+            relays: relays.into_iter().map(Arc::new).collect(),
+            verifier: BidVerifier::new(spec),
+            min_bid: Uint256::zero(),
+            timeouts: Timeouts::default(),
+            health_policy: HealthPolicy::default(),
+            bids: Mutex::new(BTreeMap::new()),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Check that bids are for the timestamp of the requested slot.
+    pub fn with_genesis_time(mut self, genesis_time: u64) -> Self {
+        self.verifier = self.verifier.with_genesis_time(genesis_time);
+        self
+    }
+
+    /// Ignore bids worth less than `min_bid` wei.
+    pub fn with_min_bid(mut self, min_bid: Uint256) -> Self {
+        self.min_bid = min_bid;
+        self
+    }
+
+    pub fn with_timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    pub fn with_health_policy(mut self, health_policy: HealthPolicy) -> Self {
+        self.health_policy = health_policy;
+        self
+    }
+
+    /// Health of every relay, in configuration order.
+    pub fn relay_stats(&self) -> Vec<RelayStats> {
+        self.relays
+            .iter()
+            .map(|relay| {
+                let health = relay.health.stats();
+                RelayStats {
+                    url: relay.url().to_owned(),
+                    healthy: self.health_policy.is_healthy(&health),
+                    score: self.health_policy.score(&health),
+                    health,
+                }
+            })
+            .collect()
+    }
+
+    /// Check the status of every relay, returning whether any is available.
+    pub async fn check_status(&self) -> bool {
+        let timeout = self.timeouts.status();
+        let mut requests = JoinSet::new();
+        for relay in &self.relays {
+            let relay = relay.clone();
+            requests.spawn(async move {
+                let ok = matches!(
+                    tokio::time::timeout(timeout, relay.client.status()).await,
+                    Ok(Ok(()))
+                );
+                relay.health.record_status(ok);
+                if !ok {
+                    warn!(relay = relay.url(), "Relay failed its status check");
+                }
+                ok
+            });
+        }
+
+        let mut available = false;
+        while let Some(joined) = requests.join_next().await {
+            available |= joined.unwrap_or(false);
+        }
+        available
+    }
+
+    /// Check the status of every relay every `interval`.
+    pub async fn check_status_every(self: Arc<Self>, interval: Duration) {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            self.check_status().await;
+        }
+    }
+
+    /// Remember which relays returned each header for `slot`, forgetting old slots.
+    fn remember_bids(&self, slot: Slot, sources: HashMap<ExecutionBlockHash, Vec<usize>>) {
+        let mut bids = self.bids.lock();
+        bids.entry(slot).or_default().extend(sources);
+        let oldest_slot = slot.saturating_sub(BID_RETENTION_SLOTS);
+        *bids = bids.split_off(&oldest_slot);
+    }
+
+    fn relays_for(&self, slot: Slot, block_hash: &ExecutionBlockHash) -> Vec<Arc<Relay>> {
+        let bids = self.bids.lock();
+        let indices = bids
+            .get(&slot)
+            .and_then(|headers| headers.get(block_hash))
+            .cloned()
+            .unwrap_or_default();
+        indices
+            .into_iter()
+            .map(|index| self.relays[index].clone())
+            .collect()
+    }
+}
+
+#[async_trait]
+impl<E: EthSpec> Proposer<E> for Multiplexer<E> {
+    async fn register_validators(
+        &self,
+        body: Vec<SignedValidatorRegistrationData>,
+    ) -> Response<()> {
+        let body = Arc::new(body);
+        let timeout = self.timeouts.register_validators();
+        let mut requests = JoinSet::new();
+        for relay in &self.relays {
+            let (relay, body) = (relay.clone(), body.clone());
+            requests.spawn(async move {
+                let result =
+                    tokio::time::timeout(timeout, relay.client.register_validators(&body)).await;
+                (relay, result)
+            });
+        }
+
+        let mut registered = false;
+        while let Some(joined) = requests.join_next().await {
+            match joined {
+                Ok((_, Ok(Ok(())))) => registered = true,
+                Ok((relay, Ok(Err(e)))) => {
+                    warn!(relay = relay.url(), error = ?e, "Failed to register validators")
+                }
+                Ok((relay, Err(_))) => {
+                    warn!(relay = relay.url(), "Timed out registering validators")
+                }
+                Err(e) => warn!(error = ?e, "Validator registration task failed"),
+            }
+        }
+
+        if registered {
+            Response::Success(())
+        } else {
+            error(502, "no relay accepted the registrations")
+        }
+    }
+
+    async fn get_header(&self, params: GetHeaderParams) -> Response<Option<GetHeaderResponse<E>>> {
+        let mut requests = JoinSet::new();
+        for (index, relay) in self.relays.iter().enumerate() {
+            if relay.health.stats().status_ok == Some(false) {
+                debug!(relay = relay.url(), "Skipping unavailable relay");
+                continue;
+            }
+            let (relay, params) = (relay.clone(), params.clone());
+            requests.spawn(async move {
+                let start = Instant::now();
+                let request =
+                    relay
+                        .client
+                        .get_header::<E>(params.slot, params.parent_hash, params.pubkey);
+                let result = tokio::time::timeout(relay.get_header_timeout, request).await;
+                (index, result, start.elapsed())
+            });
+        }
+
+        let mut best: Option<GetHeaderResponse<E>> = None;
+        let mut best_unhealthy: Option<GetHeaderResponse<E>> = None;
+        let mut sources = HashMap::<ExecutionBlockHash, Vec<usize>>::new();
+        while let Some(joined) = requests.join_next().await {
+            let (index, result, latency) = match joined {
+                Ok(joined) => joined,
+                Err(e) => {
+                    warn!(error = ?e, "Get header task failed");
+                    continue;
+                }
+            };
+            let relay = &self.relays[index];
+            // Judged before recording this request, so one failure does not discard a bid.
+            let healthy = self.health_policy.is_healthy(&relay.health.stats());
+            let response = match result {
+                Ok(Ok(Some(response))) => response,
+                Ok(Ok(None)) => {
+                    relay.health.record(Outcome::Success(latency));
+                    continue;
+                }
+                Ok(Err(e)) => {
+                    warn!(relay = relay.url(), error = ?e, "Failed to get header");
+                    relay.health.record(Outcome::Error);
+                    continue;
+                }
+                Err(_) => {
+                    warn!(relay = relay.url(), "Timed out getting header");
+                    relay.health.record(Outcome::Timeout);
+                    continue;
+                }
+            };
+            if let Err(e) = self.verifier.verify(&response.data, &params, &relay.pubkey) {
+                warn!(relay = relay.url(), error = ?e, "Ignoring invalid bid");
+                relay.health.record(Outcome::Error);
+                continue;
+            }
+            relay.health.record(Outcome::Success(latency));
+
+            let bid = response.data.message();
+            debug!(relay = relay.url(), value = %bid.value(), "Received bid");
+            if *bid.value() < self.min_bid {
+                continue;
+            }
+            sources
+                .entry(bid.header().block_hash())
+                .or_default()
+                .push(index);
+            let target = if healthy {
+                &mut best
+            } else {
+                &mut best_unhealthy
+            };
+            if target
+                .as_ref()
+                .is_none_or(|best| bid.value() > best.data.message().value())
+            {
+                *target = Some(response);
+            }
+        }
+
+        let best = best.or(best_unhealthy);
+        if best.is_some() {
+            self.remember_bids(params.slot, sources);
+        }
+        Response::Success(best)
+    }
+
+    async fn submit_blinded_block(
+        &self,
+        body: SignedBlindedBeaconBlock<E>,
+    ) -> Response<SubmitBlindedBlockResponse<E>> {
+        let slot = body.message().slot();
+        let Ok(block_hash) = body
+            .message()
+            .body()
+            .execution_payload()
+            .map(|payload| payload.block_hash())
+        else {
+            return error(400, "block has no execution payload");
+        };
+        let relays = self.relays_for(slot, &block_hash);
+        if relays.is_empty() {
+            return error(
+                400,
+                format!("no relay returned a header for {block_hash:?}"),
+            );
+        }
+
+        let body = Arc::new(body);
+        let timeout = self.timeouts.submit_blinded_block();
+        let mut requests = JoinSet::new();
+        for relay in relays {
+            let body = body.clone();
+            requests.spawn(async move {
+                let result =
+                    tokio::time::timeout(timeout, relay.client.submit_blinded_block(&body)).await;
+                (relay, result)
+            });
+        }
+
+        // The first payload matching the block wins, dropping `requests` cancels the others.
+        while let Some(joined) = requests.join_next().await {
+            match joined {
+                Ok((relay, Ok(Ok(response)))) => {
+                    match unblind_block((*body).clone(), response.data.clone()) {
+                        Ok(_) => return Response::Success(response),
+                        Err(e) => warn!(
+                            relay = relay.url(),
+                            error = %e,
+                            "Ignoring payload not matching the blinded block"
+                        ),
+                    }
+                }
+                Ok((relay, Ok(Err(e)))) => {
+                    warn!(relay = relay.url(), error = ?e, "Failed to submit blinded block")
+                }
+                Ok((relay, Err(_))) => {
+                    warn!(relay = relay.url(), "Timed out submitting blinded block")
+                }
+                Err(e) => warn!(error = ?e, "Blinded block submission task failed"),
+            }
+        }
+        error(502, "no relay returned the payload")
+    }
+
+    async fn status(&self) -> Response<()> {
+        if self.check_status().await {
+            Response::Success(())
+        } else {
+            error(503, "no relay available")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use builder_api_types::{PayloadResponse, VersionedResponse};
+    use relay_server::{
+        builder::Builder, data::Data, in_memory::InMemoryRelay, server::proposer_router,
+        BidTraceV1, GetDeliveredPayloadsQueryParams, SubmitBlockQueryParams, SubmitBlockRequest,
+        SubmitBlockRequestCapella,
+    };
+    use types::{
+        Address, BeaconBlock, BeaconBlockCapella, BlindedPayload, BlindedPayloadCapella,
+        EmptyBlock, ExecutionPayloadCapella, Hash256, Keypair, MainnetEthSpec, PublicKeyBytes,
+        Signature, ValidatorRegistrationData,
+    };
+
+    use super::*;
+
+    type E = MainnetEthSpec;
+
+    const SLOT: u64 = 10;
+    const GAS_LIMIT: u64 = 30_000_000;
+
+    fn block_hash(byte: u8) -> ExecutionBlockHash {
+        ExecutionBlockHash::from_root(Hash256::repeat_byte(byte))
+    }
+
+    fn genesis_validators_root() -> Hash256 {
+        Hash256::repeat_byte(9)
+    }
+
+    fn params(proposer: &Keypair) -> GetHeaderParams {
+        GetHeaderParams {
+            slot: Slot::new(SLOT),
+            parent_hash: block_hash(0),
+            pubkey: proposer.pk.compress(),
+        }
+    }
+
+    fn payload(block: u8) -> ExecutionPayloadCapella<E> {
+        ExecutionPayloadCapella {
+            parent_hash: block_hash(0),
+            block_hash: block_hash(block),
+            gas_limit: GAS_LIMIT,
+            ..Default::default()
+        }
+    }
+
+    /// An in-memory relay expecting `proposer` at `SLOT` and the key it signs bids with.
+    async fn relay(proposer: &Keypair) -> (InMemoryRelay<E>, Keypair) {
+        let keypair = Keypair::random();
+        let relay = InMemoryRelay::<E>::new().with_signing_key(
+            keypair.sk.clone(),
+            E::default_spec(),
+            genesis_validators_root(),
+        );
+        let pubkey = proposer.pk.compress();
+        relay.set_proposer_duty(Slot::new(SLOT), 0, pubkey);
+        relay
+            .register_validator(SignedValidatorRegistrationData {
+                message: ValidatorRegistrationData {
+                    fee_recipient: Address::repeat_byte(1),
+                    gas_limit: GAS_LIMIT,
+                    timestamp: 0,
+                    pubkey,
+                },
+                signature: Signature::empty(),
+            })
+            .await
+            .unwrap();
+        (relay, keypair)
+    }
+
+    /// Serve `api` on a local port, returning the multiplexer's view of it.
+    async fn serve<A: Proposer<E> + Send + Sync + 'static>(api: A, pubkey: PublicKey) -> Relay {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let router = proposer_router::<_, A, E>(Arc::new(api));
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        Relay::new(url, pubkey)
+    }
+
+    /// An in-memory relay expecting `proposer` at `SLOT`, served on a local port, and the
+    /// multiplexer's view of it.
+    async fn serve_relay(proposer: &Keypair) -> (InMemoryRelay<E>, Relay) {
+        let (relay, keypair) = relay(proposer).await;
+        let served = serve(relay.clone(), keypair.pk).await;
+        (relay, served)
+    }
+
+    /// A relay returning payloads with a different gas usage than the block commits to.
+    struct TamperingRelay(InMemoryRelay<E>);
+
+    #[async_trait]
+    impl Proposer<E> for TamperingRelay {
+        async fn register_validators(
+            &self,
+            body: Vec<SignedValidatorRegistrationData>,
+        ) -> Response<()> {
+            self.0.register_validators(body).await
+        }
+
+        async fn get_header(
+            &self,
+            params: GetHeaderParams,
+        ) -> Response<Option<GetHeaderResponse<E>>> {
+            self.0.get_header(params).await
+        }
+
+        async fn submit_blinded_block(
+            &self,
+            body: SignedBlindedBeaconBlock<E>,
+        ) -> Response<SubmitBlindedBlockResponse<E>> {
+            let mut response = self.0.submit_blinded_block(body).await;
+            if let Response::Success(VersionedResponse {
+                data: PayloadResponse::Capella(payload),
+                ..
+            }) = &mut response
+            {
+                payload.gas_used += 1;
+            }
+            response
+        }
+
+        async fn status(&self) -> Response<()> {
+            self.0.status().await
+        }
+    }
+
+    /// Submit a bid of `value` wei for block `block` to `relay`.
+    async fn submit(relay: &InMemoryRelay<E>, proposer: &Keypair, block: u8, value: u64) {
+        let submission = SubmitBlockRequest::Capella(SubmitBlockRequestCapella {
+            message: BidTraceV1 {
+                slot: Slot::new(SLOT),
+                parent_hash: block_hash(0),
+                block_hash: block_hash(block),
+                builder_pubkey: PublicKeyBytes::empty(),
+                proposer_pubkey: proposer.pk.compress(),
+                proposer_fee_recipient: Address::repeat_byte(1),
+                gas_limit: GAS_LIMIT,
+                gas_used: 0,
+                value: Uint256::from(value),
+                block_number: 1,
+                num_tx: 0,
+            },
+            execution_payload: payload(block),
+            signature: Signature::empty(),
+        });
+        let query_params = SubmitBlockQueryParams {
+            cancellations: None,
+        };
+        assert_eq!(
+            relay.submit_block(query_params, submission).await,
+            Response::Success(())
+        );
+    }
+
+    async fn delivered(relay: &InMemoryRelay<E>) -> usize {
+        let query_params = GetDeliveredPayloadsQueryParams {
+            slot: Some(Slot::new(SLOT)),
+            cursor: None,
+            limit: None,
+            block_hash: None,
+            block_number: None,
+            proposer_pubkey: None,
+            builder_pubkey: None,
+            order_by: None,
+        };
+        match relay.get_delivered_payloads(query_params).await {
+            Response::Success(payloads) => payloads.len(),
+            Response::Error(e) => panic!("unable to get delivered payloads: {e:?}"),
+        }
+    }
+
+    /// A blinded block for block `block` at `SLOT`, signed by `proposer`.
+    fn blinded_block(proposer: &Keypair, block: u8) -> SignedBlindedBeaconBlock<E> {
+        let spec = E::default_spec();
+        let mut blinded = BeaconBlockCapella::<E, BlindedPayload<E>>::empty(&spec);
+        blinded.slot = Slot::new(SLOT);
+        blinded.body.execution_payload = BlindedPayloadCapella {
+            execution_payload_header: (&payload(block)).into(),
+        };
+        let fork = spec.fork_at_epoch(blinded.slot.epoch(E::slots_per_epoch()));
+        BeaconBlock::Capella(blinded).sign(&proposer.sk, &fork, genesis_validators_root(), &spec)
+    }
+
+    fn bid_value(response: &Response<Option<GetHeaderResponse<E>>>) -> Option<Uint256> {
+        match response {
+            Response::Success(bid) => bid.as_ref().map(|bid| *bid.data.message().value()),
+            Response::Error(e) => panic!("unable to get header: {e:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn highest_valid_bid() {
+        let proposer = Keypair::random();
+        let (relay_a, a) = serve_relay(&proposer).await;
+        let (relay_b, b) = serve_relay(&proposer).await;
+        let (relay_c, mut c) = serve_relay(&proposer).await;
+        submit(&relay_a, &proposer, 3, 100).await;
+        submit(&relay_b, &proposer, 4, 200).await;
+        submit(&relay_c, &proposer, 5, 300).await;
+        // Bids are checked against the key a relay is configured with.
+        c.pubkey = Keypair::random().pk;
+
+        let multiplexer = Multiplexer::<E>::new(vec![a, b, c], &E::default_spec());
+        let response = multiplexer.get_header(params(&proposer)).await;
+        assert_eq!(bid_value(&response), Some(Uint256::from(200)));
+    }
+
+    #[tokio::test]
+    async fn unavailable_relay_skipped() {
+        let proposer = Keypair::random();
+        let (relay_a, a) = serve_relay(&proposer).await;
+        let (relay_b, b) = serve_relay(&proposer).await;
+        submit(&relay_a, &proposer, 3, 300).await;
+        submit(&relay_b, &proposer, 4, 100).await;
+        a.health.record_status(false);
+
+        let multiplexer = Multiplexer::<E>::new(vec![a, b], &E::default_spec());
+        let response = multiplexer.get_header(params(&proposer)).await;
+        assert_eq!(bid_value(&response), Some(Uint256::from(100)));
+        assert_eq!(multiplexer.relays[0].health.stats().requests, 0);
+        assert_eq!(multiplexer.relays[1].health.stats().requests, 1);
+    }
+
+    #[tokio::test]
+    async fn healthy_bid_preferred() {
+        let proposer = Keypair::random();
+        let (relay_a, a) = serve_relay(&proposer).await;
+        let (relay_b, b) = serve_relay(&proposer).await;
+        submit(&relay_a, &proposer, 3, 300).await;
+        // Relay A failed every recent request.
+        let policy = HealthPolicy::default();
+        for _ in 0..policy.min_samples {
+            a.health.record(Outcome::Error);
+        }
+
+        // Without a healthy bid the unhealthy relay's bid wins.
+        let multiplexer = Multiplexer::<E>::new(vec![a, b], &E::default_spec());
+        let response = multiplexer.get_header(params(&proposer)).await;
+        assert_eq!(bid_value(&response), Some(Uint256::from(300)));
+
+        // A lower bid of a healthy relay beats it.
+        submit(&relay_b, &proposer, 4, 100).await;
+        let response = multiplexer.get_header(params(&proposer)).await;
+        assert_eq!(bid_value(&response), Some(Uint256::from(100)));
+
+        let stats = multiplexer.relay_stats();
+        assert_eq!(
+            stats.iter().map(|stats| stats.healthy).collect::<Vec<_>>(),
+            vec![false, true]
+        );
+    }
+
+    #[tokio::test]
+    async fn min_bid() {
+        let proposer = Keypair::random();
+        let (relay, a) = serve_relay(&proposer).await;
+        submit(&relay, &proposer, 3, 100).await;
+
+        let multiplexer =
+            Multiplexer::<E>::new(vec![a], &E::default_spec()).with_min_bid(Uint256::from(150));
+        let response = multiplexer.get_header(params(&proposer)).await;
+        assert_eq!(bid_value(&response), None);
+    }
+
+    #[tokio::test]
+    async fn blinded_block_routing() {
+        let spec = E::default_spec();
+        let proposer = Keypair::random();
+        let (relay_a, a) = serve_relay(&proposer).await;
+        let (relay_b, b) = serve_relay(&proposer).await;
+        // Both relays have block 4, only relay B bids it.
+        submit(&relay_a, &proposer, 3, 100).await;
+        submit(&relay_a, &proposer, 4, 50).await;
+        submit(&relay_b, &proposer, 4, 200).await;
+
+        let multiplexer = Multiplexer::<E>::new(vec![a, b], &spec);
+        let response = multiplexer.get_header(params(&proposer)).await;
+        assert_eq!(bid_value(&response), Some(Uint256::from(200)));
+
+        let block = blinded_block(&proposer, 4);
+        let Response::Success(response) = multiplexer.submit_blinded_block(block).await else {
+            panic!("no payload returned");
+        };
+        assert_eq!(response.data, PayloadResponse::Capella(payload(4)));
+        assert_eq!(delivered(&relay_a).await, 0);
+        assert_eq!(delivered(&relay_b).await, 1);
+    }
+
+    #[tokio::test]
+    async fn mismatched_payload_ignored() {
+        let spec = E::default_spec();
+        let proposer = Keypair::random();
+        let (tampering, keypair) = relay(&proposer).await;
+        submit(&tampering, &proposer, 4, 200).await;
+        let a = serve(TamperingRelay(tampering), keypair.pk).await;
+
+        // The only payload does not match the block.
+        let multiplexer = Multiplexer::<E>::new(vec![a], &spec);
+        let response = multiplexer.get_header(params(&proposer)).await;
+        assert_eq!(bid_value(&response), Some(Uint256::from(200)));
+        assert!(matches!(
+            multiplexer
+                .submit_blinded_block(blinded_block(&proposer, 4))
+                .await,
+            Response::Error(ErrorResponse { code: 502, .. })
+        ));
+
+        // Another relay with the same block returns the right one.
+        let (tampering, keypair) = relay(&proposer).await;
+        submit(&tampering, &proposer, 4, 200).await;
+        let a = serve(TamperingRelay(tampering), keypair.pk).await;
+        let (honest, b) = serve_relay(&proposer).await;
+        submit(&honest, &proposer, 4, 100).await;
+
+        let multiplexer = Multiplexer::<E>::new(vec![a, b], &spec);
+        let response = multiplexer.get_header(params(&proposer)).await;
+        assert_eq!(bid_value(&response), Some(Uint256::from(200)));
+        let Response::Success(response) = multiplexer
+            .submit_blinded_block(blinded_block(&proposer, 4))
+            .await
+        else {
+            panic!("no payload returned");
+        };
+        assert_eq!(response.data, PayloadResponse::Capella(payload(4)));
+        assert_eq!(delivered(&honest).await, 1);
+    }
+}
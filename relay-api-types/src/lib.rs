@@ -1,10 +1,16 @@
-use serde::{Deserialize, Serialize};
+use builder_api_types::{
+    BlobsBundle, BuilderBid, BuilderBidBellatrix, BuilderBidCapella, BuilderBidDeneb,
+    BuilderBidElectra, ExecutionPayloadAndBlobsBundleDeneb, ExecutionPayloadAndBlobsBundleElectra,
+    ForkVersionDecode, PayloadResponse, SignedBuilderBid, VersionedResponse,
+};
+use serde::{de::Error as _, Deserialize, Serialize};
 use serde_utils::quoted_u64::Quoted;
+use ssz::Decode as _;
 use ssz_derive::{Decode, Encode};
 use types::{
-    superstruct, Address, EthSpec, ExecutionBlockHash, ExecutionPayloadBellatrix,
-    ExecutionPayloadCapella, ExecutionPayloadDeneb, ExecutionPayloadElectra, PublicKeyBytes,
-    Signature, SignedValidatorRegistrationData, Slot, Uint256,
+    superstruct, Address, ChainSpec, EthSpec, ExecutionBlockHash, ExecutionPayloadBellatrix,
+    ExecutionPayloadCapella, ExecutionPayloadDeneb, ExecutionPayloadElectra, ForkName,
+    PublicKeyBytes, SecretKey, Signature, SignedValidatorRegistrationData, Slot, Uint256,
 };
 
 // Builder API requests
@@ -28,10 +34,122 @@ pub struct SubmitBlockQueryParams {
 #[serde(bound = "E: EthSpec", untagged)]
 #[ssz(enum_behaviour = "transparent")]
 pub struct SubmitBlockRequest<E: EthSpec> {
-    message: BidTraceV1,
+    pub message: BidTraceV1,
     #[superstruct(flatten)]
-    execution_payload: ExecutionPayload<E>,
-    signature: Signature,
+    pub execution_payload: ExecutionPayload<E>,
+    #[superstruct(only(Deneb, Electra))]
+    pub blobs_bundle: BlobsBundle<E>,
+    pub signature: Signature,
+}
+
+impl<E: EthSpec> SubmitBlockRequest<E> {
+    /// The bid offered to proposers for this submission, naming the relay's `pubkey`.
+    pub fn builder_bid(&self, pubkey: PublicKeyBytes) -> BuilderBid<E> {
+        let value = self.message().value;
+        match self {
+            Self::Bellatrix(submission) => BuilderBid::Bellatrix(BuilderBidBellatrix {
+                header: (&submission.execution_payload).into(),
+                value,
+                pubkey,
+            }),
+            Self::Capella(submission) => BuilderBid::Capella(BuilderBidCapella {
+                header: (&submission.execution_payload).into(),
+                value,
+                pubkey,
+            }),
+            Self::Deneb(submission) => BuilderBid::Deneb(BuilderBidDeneb {
+                header: (&submission.execution_payload).into(),
+                blob_kzg_commitments: submission.blobs_bundle.commitments.clone(),
+                value,
+                pubkey,
+            }),
+            Self::Electra(submission) => BuilderBid::Electra(BuilderBidElectra {
+                header: (&submission.execution_payload).into(),
+                blob_kzg_commitments: submission.blobs_bundle.commitments.clone(),
+                value,
+                pubkey,
+            }),
+        }
+    }
+
+    /// [`builder_bid`](Self::builder_bid) signed with the relay's `secret_key`, whose public key
+    /// is `pubkey`.
+    pub fn signed_builder_bid(
+        &self,
+        pubkey: PublicKeyBytes,
+        secret_key: &SecretKey,
+        spec: &ChainSpec,
+    ) -> SignedBuilderBid<E> {
+        self.builder_bid(pubkey).sign(secret_key, spec)
+    }
+
+    pub fn fork_name(&self) -> ForkName {
+        match self {
+            Self::Bellatrix(_) => ForkName::Bellatrix,
+            Self::Capella(_) => ForkName::Capella,
+            Self::Deneb(_) => ForkName::Deneb,
+            Self::Electra(_) => ForkName::Electra,
+        }
+    }
+
+    /// The payload returned to the proposer once it signed the bid for this submission.
+    pub fn payload_response(&self) -> PayloadResponse<E> {
+        match self {
+            Self::Bellatrix(submission) => {
+                PayloadResponse::Bellatrix(submission.execution_payload.clone())
+            }
+            Self::Capella(submission) => {
+                PayloadResponse::Capella(submission.execution_payload.clone())
+            }
+            Self::Deneb(submission) => {
+                PayloadResponse::Deneb(ExecutionPayloadAndBlobsBundleDeneb {
+                    execution_payload: submission.execution_payload.clone(),
+                    blobs_bundle: submission.blobs_bundle.clone(),
+                })
+            }
+            Self::Electra(submission) => {
+                PayloadResponse::Electra(ExecutionPayloadAndBlobsBundleElectra {
+                    execution_payload: submission.execution_payload.clone(),
+                    blobs_bundle: submission.blobs_bundle.clone(),
+                })
+            }
+        }
+    }
+}
+
+impl<E: EthSpec> ForkVersionDecode for SubmitBlockRequest<E> {
+    fn from_json_value_by_fork(
+        value: serde_json::Value,
+        fork_name: ForkName,
+    ) -> Result<Self, serde_json::Error> {
+        match fork_name {
+            ForkName::Bellatrix => serde_json::from_value(value).map(Self::Bellatrix),
+            ForkName::Capella => serde_json::from_value(value).map(Self::Capella),
+            ForkName::Deneb => serde_json::from_value(value).map(Self::Deneb),
+            ForkName::Electra => serde_json::from_value(value).map(Self::Electra),
+            fork_name => Err(serde_json::Error::custom(format!(
+                "block submissions are not supported before Bellatrix, got {fork_name}"
+            ))),
+        }
+    }
+
+    fn from_ssz_bytes_by_fork(bytes: &[u8], fork_name: ForkName) -> Result<Self, ssz::DecodeError> {
+        match fork_name {
+            ForkName::Bellatrix => {
+                SubmitBlockRequestBellatrix::from_ssz_bytes(bytes).map(Self::Bellatrix)
+            }
+            ForkName::Capella => {
+                SubmitBlockRequestCapella::from_ssz_bytes(bytes).map(Self::Capella)
+            }
+            ForkName::Deneb => SubmitBlockRequestDeneb::from_ssz_bytes(bytes).map(Self::Deneb),
+            ForkName::Electra => {
+                SubmitBlockRequestElectra::from_ssz_bytes(bytes).map(Self::Electra)
+            }
+            fork_name => Err(ssz::DecodeError::BytesInvalid(format!(
+                "block submissions are not supported before Bellatrix, got {fork_name}"
+            ))),
+        }
+    }
 }
 
 impl<E: EthSpec> ssz::Decode for SubmitBlockRequest<E> {
@@ -105,6 +223,22 @@ pub struct GetValidatorRegistrationQueryParams {
     pub pubkey: PublicKeyBytes,
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GetBuilderDemotionsQueryParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot: Option<Slot>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub builder_pubkey: Option<PublicKeyBytes>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GetArchivedPayloadQueryParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot: Option<Slot>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_hash: Option<ExecutionBlockHash>,
+}
+
 // Builder API responses
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ValidatorsResponse {
@@ -156,6 +290,135 @@ pub struct BidTraceV2WithTimestamp {
     pub timestamp_ms: i64,
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuilderDemotion {
+    pub slot: Slot,
+    pub builder_pubkey: PublicKeyBytes,
+    pub block_hash: ExecutionBlockHash,
+    #[serde(with = "serde_utils::quoted_u256")]
+    pub value: Uint256,
+    pub reason: String,
+    #[serde(with = "serde_utils::quoted_i64")]
+    pub timestamp_ms: i64,
+}
+
+// Builder status
+
+/// Permissions a relay grants a builder.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuilderStatus {
+    /// Bids up to the builder's collateral are accepted before simulation completes.
+    pub is_optimistic: bool,
+    /// All submissions are rejected.
+    pub is_blacklisted: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BuilderInfo {
+    #[serde(flatten)]
+    pub status: BuilderStatus,
+    /// Value, in wei, the builder has put up to cover optimistically accepted invalid blocks.
+    #[serde(with = "serde_utils::quoted_u256")]
+    pub collateral: Uint256,
+}
+
+// Admin API requests
+
+/// Changes to a builder. Fields that are not set are left unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UpdateBuilderRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_optimistic: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_blacklisted: Option<bool>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "quoted_u256_option"
+    )]
+    pub collateral: Option<Uint256>,
+}
+
+impl UpdateBuilderRequest {
+    pub fn apply(&self, info: &mut BuilderInfo) {
+        if let Some(is_optimistic) = self.is_optimistic {
+            info.status.is_optimistic = is_optimistic;
+        }
+        if let Some(is_blacklisted) = self.is_blacklisted {
+            info.status.is_blacklisted = is_blacklisted;
+        }
+        if let Some(collateral) = self.collateral {
+            info.collateral = collateral;
+        }
+    }
+}
+
+mod quoted_u256_option {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use types::Uint256;
+
+    #[derive(Serialize, Deserialize)]
+    struct QuotedU256(#[serde(with = "serde_utils::quoted_u256")] Uint256);
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<Uint256>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        value.map(QuotedU256).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Uint256>, D::Error> {
+        Ok(Option::<QuotedU256>::deserialize(deserializer)?.map(|quoted| quoted.0))
+    }
+}
+
+// Admin API responses
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuilderEntry {
+    pub builder_pubkey: PublicKeyBytes,
+    #[serde(flatten)]
+    pub info: BuilderInfo,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub api_key: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReceivedBidTrace {
+    #[serde(flatten)]
+    pub bid_trace: BidTraceV2WithTimestamp,
+    /// Milliseconds from the start of the bid's slot until the relay received it. Negative for
+    /// bids received before their slot started.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "quoted_i64_option"
+    )]
+    pub ms_into_slot: Option<i64>,
+}
+
+mod quoted_i64_option {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct QuotedI64(#[serde(with = "serde_utils::quoted_i64")] i64);
+
+    pub fn serialize<S: Serializer>(value: &Option<i64>, serializer: S) -> Result<S::Ok, S::Error> {
+        value.map(QuotedI64).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<i64>, D::Error> {
+        Ok(Option::<QuotedI64>::deserialize(deserializer)?.map(|quoted| quoted.0))
+    }
+}
+
 // Response types common
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -181,4 +444,165 @@ pub type SubmitBlockResponse = Response<()>;
 // Data API response types
 pub type GetDeliveredPayloadsResponse = Response<Vec<BidTraceV2WithTimestamp>>;
 pub type GetReceivedBidsResponse = Response<Vec<BidTraceV2>>;
+/// Received bids with their receive time. Each entry also decodes as a [`BidTraceV2`].
+pub type GetReceivedBidTracesResponse = Response<Vec<ReceivedBidTrace>>;
 pub type GetValidatorRegistrationResponse = Response<SignedValidatorRegistrationData>;
+pub type GetBuilderDemotionsResponse = Response<Vec<BuilderDemotion>>;
+pub type GetArchivedPayloadResponse<E> = Response<VersionedResponse<SubmitBlockRequest<E>>>;
+
+// Admin API response types
+pub type ListBuildersResponse = Response<Vec<BuilderEntry>>;
+pub type GetBuilderResponse = Response<BuilderEntry>;
+pub type UpdateBuilderResponse = Response<BuilderEntry>;
+pub type RotateApiKeyResponse = Response<ApiKey>;
+
+#[cfg(test)]
+mod tests {
+    use builder_api_types::verify::BidVerifier;
+    use tree_hash::TreeHash;
+    use types::{ForkName, Hash256, Keypair, KzgCommitment, KzgCommitments, MainnetEthSpec};
+
+    use super::*;
+
+    type E = MainnetEthSpec;
+
+    fn trace() -> BidTraceV1 {
+        BidTraceV1 {
+            slot: Slot::new(10),
+            parent_hash: ExecutionBlockHash::from_root(Hash256::repeat_byte(1)),
+            block_hash: ExecutionBlockHash::from_root(Hash256::repeat_byte(2)),
+            builder_pubkey: PublicKeyBytes::empty(),
+            proposer_pubkey: PublicKeyBytes::empty(),
+            proposer_fee_recipient: Address::repeat_byte(3),
+            gas_limit: 30_000_000,
+            gas_used: 21_000,
+            value: Uint256::from(100),
+            block_number: 1,
+            num_tx: 1,
+        }
+    }
+
+    fn commitments() -> KzgCommitments<E> {
+        KzgCommitments::<E>::new(vec![KzgCommitment([7; 48]), KzgCommitment([8; 48])]).unwrap()
+    }
+
+    fn blobs_bundle() -> BlobsBundle<E> {
+        BlobsBundle {
+            commitments: commitments(),
+            proofs: Default::default(),
+            blobs: Default::default(),
+        }
+    }
+
+    /// A submission of `trace` for every fork.
+    fn submissions() -> Vec<SubmitBlockRequest<E>> {
+        let message = trace();
+        let (parent_hash, block_hash) = (message.parent_hash, message.block_hash);
+        let (fee_recipient, gas_limit) = (message.proposer_fee_recipient, message.gas_limit);
+        vec![
+            SubmitBlockRequest::Bellatrix(SubmitBlockRequestBellatrix {
+                message: message.clone(),
+                execution_payload: ExecutionPayloadBellatrix {
+                    parent_hash,
+                    fee_recipient,
+                    gas_limit,
+                    block_hash,
+                    ..Default::default()
+                },
+                signature: Signature::empty(),
+            }),
+            SubmitBlockRequest::Capella(SubmitBlockRequestCapella {
+                message: message.clone(),
+                execution_payload: ExecutionPayloadCapella {
+                    parent_hash,
+                    fee_recipient,
+                    gas_limit,
+                    block_hash,
+                    ..Default::default()
+                },
+                signature: Signature::empty(),
+            }),
+            SubmitBlockRequest::Deneb(SubmitBlockRequestDeneb {
+                message: message.clone(),
+                execution_payload: ExecutionPayloadDeneb {
+                    parent_hash,
+                    fee_recipient,
+                    gas_limit,
+                    block_hash,
+                    blob_gas_used: 2 * 131_072,
+                    ..Default::default()
+                },
+                blobs_bundle: blobs_bundle(),
+                signature: Signature::empty(),
+            }),
+            SubmitBlockRequest::Electra(SubmitBlockRequestElectra {
+                message,
+                execution_payload: ExecutionPayloadElectra {
+                    parent_hash,
+                    fee_recipient,
+                    gas_limit,
+                    block_hash,
+                    blob_gas_used: 2 * 131_072,
+                    ..Default::default()
+                },
+                blobs_bundle: blobs_bundle(),
+                signature: Signature::empty(),
+            }),
+        ]
+    }
+
+    #[test]
+    fn builder_bid() {
+        let pubkey = Keypair::random().pk.compress();
+        for submission in submissions() {
+            let bid = submission.builder_bid(pubkey);
+
+            // A header has the hash tree root of the payload it summarizes.
+            let (payload_root, header_root) = match (&submission, &bid) {
+                (SubmitBlockRequest::Bellatrix(submission), BuilderBid::Bellatrix(bid)) => (
+                    submission.execution_payload.tree_hash_root(),
+                    bid.header.tree_hash_root(),
+                ),
+                (SubmitBlockRequest::Capella(submission), BuilderBid::Capella(bid)) => (
+                    submission.execution_payload.tree_hash_root(),
+                    bid.header.tree_hash_root(),
+                ),
+                (SubmitBlockRequest::Deneb(submission), BuilderBid::Deneb(bid)) => (
+                    submission.execution_payload.tree_hash_root(),
+                    bid.header.tree_hash_root(),
+                ),
+                (SubmitBlockRequest::Electra(submission), BuilderBid::Electra(bid)) => (
+                    submission.execution_payload.tree_hash_root(),
+                    bid.header.tree_hash_root(),
+                ),
+                _ => panic!("bid for another fork than its submission"),
+            };
+            assert_eq!(header_root, payload_root);
+
+            assert_eq!(bid.header().block_hash(), trace().block_hash);
+            assert_eq!(*bid.value(), trace().value);
+            assert_eq!(*bid.pubkey(), pubkey);
+            match bid.fork_name() {
+                ForkName::Deneb | ForkName::Electra => {
+                    assert_eq!(bid.blob_kzg_commitments(), Ok(&commitments()))
+                }
+                _ => assert!(bid.blob_kzg_commitments().is_err()),
+            }
+        }
+    }
+
+    #[test]
+    fn signed_builder_bid() {
+        let spec = E::default_spec();
+        let verifier = BidVerifier::new(&spec);
+        let keypair = Keypair::random();
+        for submission in submissions() {
+            let bid = submission.signed_builder_bid(keypair.pk.compress(), &keypair.sk, &spec);
+
+            assert_eq!(verifier.verify_signature(&bid, &keypair.pk), Ok(()));
+            assert!(verifier
+                .verify_signature(&bid, &Keypair::random().pk)
+                .is_err());
+        }
+    }
+}
@@ -1,8 +1,9 @@
 use relay_api_types::{
-    GetDeliveredPayloadsQueryParams, GetDeliveredPayloadsResponse, GetReceivedBidsQueryParams,
-    GetReceivedBidsResponse, GetValidatorRegistrationQueryParams, GetValidatorRegistrationResponse,
-    GetValidatorsResponse, SubmitBlockQueryParams, SubmitBlockRequest, SubmitBlockResponse,
-    ValidatorsResponse,
+    GetArchivedPayloadQueryParams, GetArchivedPayloadResponse, GetBuilderDemotionsQueryParams,
+    GetBuilderDemotionsResponse, GetDeliveredPayloadsQueryParams, GetDeliveredPayloadsResponse,
+    GetReceivedBidTracesResponse, GetReceivedBidsQueryParams, GetReceivedBidsResponse,
+    GetValidatorRegistrationQueryParams, GetValidatorRegistrationResponse, GetValidatorsResponse,
+    SubmitBlockQueryParams, SubmitBlockRequest, SubmitBlockResponse, ValidatorsResponse,
 };
 use reqwest::Client;
 use serde::Deserialize;
@@ -25,9 +26,13 @@ impl From<reqwest::Error> for Error {
     }
 }
 
+/// Header carrying the submitting builder's API key.
+pub const API_KEY_HEADER: &str = "X-Api-Key";
+
 pub struct RelayClient {
     client: Client,
     base_url: String,
+    api_key: Option<String>,
 }
 
 impl RelayClient {
@@ -35,9 +40,16 @@ impl RelayClient {
         Self {
             client: Client::new(),
             base_url,
+            api_key: None,
         }
     }
 
+    /// Send `api_key` with block submissions, for relays that require builders to authenticate.
+    pub fn with_api_key(mut self, api_key: String) -> Self {
+        self.api_key = Some(api_key);
+        self
+    }
+
     async fn build_response<T>(&self, response: reqwest::Response) -> Result<T, Error>
     where
         T: for<'de> Deserialize<'de>,
@@ -64,13 +76,11 @@ impl RelayClient {
         E: EthSpec,
     {
         let url = format!("{}/relay/v1/builder/blocks", self.base_url);
-        let response = self
-            .client
-            .post(&url)
-            .query(&query_params)
-            .json(&body)
-            .send()
-            .await?;
+        let mut request = self.client.post(&url).query(&query_params).json(&body);
+        if let Some(api_key) = &self.api_key {
+            request = request.header(API_KEY_HEADER, api_key);
+        }
+        let response = request.send().await?;
 
         self.build_response(response).await
     }
@@ -111,6 +121,21 @@ impl RelayClient {
         self.build_response(response).await
     }
 
+    /// Like [`Self::get_received_bids`], including when the relay received each bid, if it
+    /// reports it.
+    pub async fn get_received_bid_traces(
+        &self,
+        query_params: GetReceivedBidsQueryParams,
+    ) -> Result<GetReceivedBidTracesResponse, Error> {
+        let url = format!(
+            "{}/relay/v1/data/bidtraces/builder_blocks_received",
+            self.base_url
+        );
+        let response = self.client.get(&url).query(&query_params).send().await?;
+
+        self.build_response(response).await
+    }
+
     pub async fn get_validator_registration(
         &self,
         query_params: GetValidatorRegistrationQueryParams,
@@ -120,6 +145,29 @@ impl RelayClient {
 
         self.build_response(response).await
     }
+
+    pub async fn get_builder_demotions(
+        &self,
+        query_params: GetBuilderDemotionsQueryParams,
+    ) -> Result<GetBuilderDemotionsResponse, Error> {
+        let url = format!("{}/relay/v1/data/builder_demotions", self.base_url);
+        let response = self.client.get(&url).query(&query_params).send().await?;
+
+        self.build_response(response).await
+    }
+
+    pub async fn get_archived_payload<E>(
+        &self,
+        query_params: GetArchivedPayloadQueryParams,
+    ) -> Result<GetArchivedPayloadResponse<E>, Error>
+    where
+        E: EthSpec,
+    {
+        let url = format!("{}/relay/v1/data/archive/payload", self.base_url);
+        let response = self.client.get(&url).query(&query_params).send().await?;
+
+        self.build_response(response).await
+    }
 }
 
 #[test]
@@ -0,0 +1,38 @@
+use async_trait::async_trait;
+use relay_api_types::{
+    GetBuilderResponse, ListBuildersResponse, RotateApiKeyResponse, UpdateBuilderRequest,
+    UpdateBuilderResponse,
+};
+use types::PublicKeyBytes;
+
+/// BuilderRegistry
+#[async_trait]
+pub trait BuilderRegistry {
+    /// List all builders known to the relay..
+    ///
+    /// ListBuilders - GET /relay/v1/admin/builders
+    async fn list_builders(&self) -> ListBuildersResponse;
+
+    /// Get the status and collateral of a builder..
+    ///
+    /// GetBuilder - GET /relay/v1/admin/builders/{builder_pubkey}
+    async fn get_builder(&self, builder_pubkey: PublicKeyBytes) -> GetBuilderResponse;
+
+    /// Change the status or collateral of a builder..
+    ///
+    /// UpdateBuilder - POST /relay/v1/admin/builders/{builder_pubkey}
+    async fn update_builder(
+        &self,
+        builder_pubkey: PublicKeyBytes,
+        body: UpdateBuilderRequest,
+    ) -> UpdateBuilderResponse;
+
+    /// Replace the API key of a builder, returning the new key..
+    ///
+    /// RotateApiKey - POST /relay/v1/admin/builders/{builder_pubkey}/api_key
+    async fn rotate_api_key(&self, builder_pubkey: PublicKeyBytes) -> RotateApiKeyResponse;
+
+    /// Whether `api_key` is the current API key of `builder_pubkey`. Builders without an API key
+    /// are never authenticated.
+    fn verify_api_key(&self, builder_pubkey: &PublicKeyBytes, api_key: &str) -> bool;
+}
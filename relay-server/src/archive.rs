@@ -0,0 +1,393 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use relay_api_types::{
+    GetArchivedPayloadQueryParams, GetArchivedPayloadResponse, SubmitBlockRequest,
+    SubmitBlockRequestBellatrix, SubmitBlockRequestCapella, SubmitBlockRequestDeneb,
+    SubmitBlockRequestElectra,
+};
+use ssz::{Decode, Encode};
+use tracing::warn;
+use types::{eth_spec::EthSpec, ExecutionBlockHash, ForkName, Hash256, Slot};
+
+/// Extension of archived payload files.
+pub const PAYLOAD_FILE_EXTENSION: &str = "ssz_snappy";
+
+/// Archive
+#[async_trait]
+pub trait Archive<E: EthSpec> {
+    /// Get the full submission of a delivered payload by block hash or slot..
+    ///
+    /// GetArchivedPayload - GET /relay/v1/data/archive/payload
+    async fn get_archived_payload(
+        &self,
+        query_params: GetArchivedPayloadQueryParams,
+    ) -> GetArchivedPayloadResponse<E>;
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Snappy(snap::Error),
+    Ssz(ssz::DecodeError),
+    /// An archived payload is for a fork without builder submissions.
+    UnsupportedFork(ForkName),
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<snap::Error> for Error {
+    fn from(e: snap::Error) -> Self {
+        Error::Snappy(e)
+    }
+}
+
+impl From<ssz::DecodeError> for Error {
+    fn from(e: ssz::DecodeError) -> Self {
+        Error::Ssz(e)
+    }
+}
+
+/// Keeps the full submission of every delivered payload.
+#[async_trait]
+pub trait PayloadArchive<E: EthSpec>: Send + Sync {
+    async fn insert(&self, payload: &SubmitBlockRequest<E>) -> Result<(), Error>;
+
+    async fn get_by_block_hash(
+        &self,
+        block_hash: &ExecutionBlockHash,
+    ) -> Result<Option<SubmitBlockRequest<E>>, Error>;
+
+    /// All payloads archived for `slot`, usually at most one.
+    async fn get_by_slot(&self, slot: Slot) -> Result<Vec<SubmitBlockRequest<E>>, Error>;
+
+    /// Drop payloads for slots before `slot`.
+    async fn prune(&self, slot: Slot) -> Result<(), Error>;
+}
+
+/// Snappy-compressed SSZ of `payload`.
+pub fn compress<E: EthSpec>(payload: &SubmitBlockRequest<E>) -> Result<Vec<u8>, Error> {
+    Ok(snap::raw::Encoder::new().compress_vec(&payload.as_ssz_bytes())?)
+}
+
+/// Decode a payload compressed with [`compress`]. SSZ does not identify the fork, so it has to
+/// be stored alongside.
+pub fn decompress<E: EthSpec>(
+    bytes: &[u8],
+    fork_name: ForkName,
+) -> Result<SubmitBlockRequest<E>, Error> {
+    let bytes = snap::raw::Decoder::new().decompress_vec(bytes)?;
+    let payload = match fork_name {
+        ForkName::Bellatrix => {
+            SubmitBlockRequest::Bellatrix(SubmitBlockRequestBellatrix::from_ssz_bytes(&bytes)?)
+        }
+        ForkName::Capella => {
+            SubmitBlockRequest::Capella(SubmitBlockRequestCapella::from_ssz_bytes(&bytes)?)
+        }
+        ForkName::Deneb => {
+            SubmitBlockRequest::Deneb(SubmitBlockRequestDeneb::from_ssz_bytes(&bytes)?)
+        }
+        ForkName::Electra => {
+            SubmitBlockRequest::Electra(SubmitBlockRequestElectra::from_ssz_bytes(&bytes)?)
+        }
+        fork_name => return Err(Error::UnsupportedFork(fork_name)),
+    };
+    Ok(payload)
+}
+
+/// Where an archived payload is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Entry {
+    slot: Slot,
+    fork_name: ForkName,
+}
+
+/// Archived payloads by block hash and slot.
+#[derive(Default)]
+struct Index {
+    by_block_hash: HashMap<ExecutionBlockHash, Entry>,
+    by_slot: BTreeMap<Slot, Vec<ExecutionBlockHash>>,
+}
+
+impl Index {
+    fn insert(&mut self, block_hash: ExecutionBlockHash, entry: Entry) {
+        if self.by_block_hash.insert(block_hash, entry).is_none() {
+            self.by_slot.entry(entry.slot).or_default().push(block_hash);
+        }
+    }
+
+    /// Remove entries for slots before `slot`, returning them.
+    fn prune(&mut self, slot: Slot) -> BTreeMap<Slot, Vec<ExecutionBlockHash>> {
+        let kept = self.by_slot.split_off(&slot);
+        let pruned = std::mem::replace(&mut self.by_slot, kept);
+        for block_hash in pruned.values().flatten() {
+            self.by_block_hash.remove(block_hash);
+        }
+        pruned
+    }
+}
+
+/// A payload archive kept in memory, lost on restart.
+#[derive(Default)]
+pub struct MemoryPayloadArchive {
+    index: RwLock<Index>,
+    payloads: RwLock<HashMap<ExecutionBlockHash, Vec<u8>>>,
+}
+
+impl MemoryPayloadArchive {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl<E: EthSpec> PayloadArchive<E> for MemoryPayloadArchive {
+    async fn insert(&self, payload: &SubmitBlockRequest<E>) -> Result<(), Error> {
+        let block_hash = payload.message().block_hash;
+        let bytes = compress(payload)?;
+        self.payloads.write().insert(block_hash, bytes);
+        self.index.write().insert(
+            block_hash,
+            Entry {
+                slot: payload.message().slot,
+                fork_name: payload.fork_name(),
+            },
+        );
+        Ok(())
+    }
+
+    async fn get_by_block_hash(
+        &self,
+        block_hash: &ExecutionBlockHash,
+    ) -> Result<Option<SubmitBlockRequest<E>>, Error> {
+        let Some(entry) = self.index.read().by_block_hash.get(block_hash).copied() else {
+            return Ok(None);
+        };
+        match self.payloads.read().get(block_hash) {
+            Some(bytes) => decompress(bytes, entry.fork_name).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    async fn get_by_slot(&self, slot: Slot) -> Result<Vec<SubmitBlockRequest<E>>, Error> {
+        let block_hashes = self
+            .index
+            .read()
+            .by_slot
+            .get(&slot)
+            .cloned()
+            .unwrap_or_default();
+        let mut payloads = Vec::with_capacity(block_hashes.len());
+        for block_hash in block_hashes {
+            payloads.extend(PayloadArchive::<E>::get_by_block_hash(self, &block_hash).await?);
+        }
+        Ok(payloads)
+    }
+
+    async fn prune(&self, slot: Slot) -> Result<(), Error> {
+        let pruned = self.index.write().prune(slot);
+        let mut payloads = self.payloads.write();
+        for block_hash in pruned.values().flatten() {
+            payloads.remove(block_hash);
+        }
+        Ok(())
+    }
+}
+
+/// A payload archive storing each payload in its own file, at
+/// `<dir>/<slot>/<block_hash>.<fork>.ssz_snappy`.
+pub struct FilePayloadArchive {
+    dir: PathBuf,
+    index: RwLock<Index>,
+}
+
+fn parse_file_name(path: &Path) -> Option<(ExecutionBlockHash, ForkName)> {
+    let mut parts = path.file_name()?.to_str()?.split('.');
+    let block_hash = serde_utils::hex::decode(parts.next()?).ok()?;
+    let fork_name = ForkName::from_str(parts.next()?).ok()?;
+    if block_hash.len() != 32 || parts.next()? != PAYLOAD_FILE_EXTENSION {
+        return None;
+    }
+    Some((
+        ExecutionBlockHash::from_root(Hash256::from_slice(&block_hash)),
+        fork_name,
+    ))
+}
+
+impl FilePayloadArchive {
+    /// Open the archive in `dir`, creating the directory if it does not exist.
+    pub fn open(dir: &Path) -> Result<Self, Error> {
+        fs::create_dir_all(dir)?;
+
+        let mut index = Index::default();
+        for slot_dir in fs::read_dir(dir)? {
+            let slot_dir = slot_dir?.path();
+            let Some(slot) = slot_dir
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.parse::<u64>().ok())
+            else {
+                warn!(path = ?slot_dir, "Ignoring unknown entry in payload archive");
+                continue;
+            };
+            for file in fs::read_dir(&slot_dir)? {
+                let path = file?.path();
+                // Also skips temporary files left behind by an interrupted write.
+                let Some((block_hash, fork_name)) = parse_file_name(&path) else {
+                    warn!(path = ?path, "Ignoring unknown file in payload archive");
+                    continue;
+                };
+                index.insert(
+                    block_hash,
+                    Entry {
+                        slot: Slot::new(slot),
+                        fork_name,
+                    },
+                );
+            }
+        }
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            index: RwLock::new(index),
+        })
+    }
+
+    fn slot_dir(&self, slot: Slot) -> PathBuf {
+        self.dir.join(slot.as_u64().to_string())
+    }
+
+    fn path(&self, block_hash: &ExecutionBlockHash, entry: Entry) -> PathBuf {
+        self.slot_dir(entry.slot).join(format!(
+            "{}.{}.{PAYLOAD_FILE_EXTENSION}",
+            serde_utils::hex::encode(block_hash.into_root().as_bytes()),
+            entry.fork_name,
+        ))
+    }
+}
+
+#[async_trait]
+impl<E: EthSpec> PayloadArchive<E> for FilePayloadArchive {
+    async fn insert(&self, payload: &SubmitBlockRequest<E>) -> Result<(), Error> {
+        let block_hash = payload.message().block_hash;
+        let entry = Entry {
+            slot: payload.message().slot,
+            fork_name: payload.fork_name(),
+        };
+
+        tokio::fs::create_dir_all(self.slot_dir(entry.slot)).await?;
+        let path = self.path(&block_hash, entry);
+        // Write to a temporary file first so a crash never leaves a truncated payload behind.
+        let tmp_path = path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, compress(payload)?).await?;
+        tokio::fs::rename(&tmp_path, &path).await?;
+
+        self.index.write().insert(block_hash, entry);
+        Ok(())
+    }
+
+    async fn get_by_block_hash(
+        &self,
+        block_hash: &ExecutionBlockHash,
+    ) -> Result<Option<SubmitBlockRequest<E>>, Error> {
+        let Some(entry) = self.index.read().by_block_hash.get(block_hash).copied() else {
+            return Ok(None);
+        };
+        let bytes = tokio::fs::read(self.path(block_hash, entry)).await?;
+        decompress(&bytes, entry.fork_name).map(Some)
+    }
+
+    async fn get_by_slot(&self, slot: Slot) -> Result<Vec<SubmitBlockRequest<E>>, Error> {
+        let block_hashes = self
+            .index
+            .read()
+            .by_slot
+            .get(&slot)
+            .cloned()
+            .unwrap_or_default();
+        let mut payloads = Vec::with_capacity(block_hashes.len());
+        for block_hash in block_hashes {
+            payloads.extend(PayloadArchive::<E>::get_by_block_hash(self, &block_hash).await?);
+        }
+        Ok(payloads)
+    }
+
+    async fn prune(&self, slot: Slot) -> Result<(), Error> {
+        let pruned = self.index.write().prune(slot);
+        for slot in pruned.keys() {
+            match tokio::fs::remove_dir_all(self.slot_dir(*slot)).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use types::MainnetEthSpec;
+
+    use super::*;
+    use crate::test_utils::{block_hash, capella_submission, deneb_submission, trace};
+
+    type E = MainnetEthSpec;
+
+    #[tokio::test]
+    async fn file_archive() {
+        let dir = std::env::temp_dir().join(format!(
+            "relay-archive-{}-{}",
+            std::process::id(),
+            rand::random::<u64>()
+        ));
+        let archive = FilePayloadArchive::open(&dir).unwrap();
+        let payloads = [
+            capella_submission::<E>(trace(10, 1, 2, 3, 100)),
+            deneb_submission::<E>(trace(11, 1, 2, 4, 100)),
+        ];
+        for payload in &payloads {
+            archive.insert(payload).await.unwrap();
+        }
+
+        // Payloads and their forks are found again after a restart.
+        let archive = FilePayloadArchive::open(&dir).unwrap();
+        let payload = PayloadArchive::<E>::get_by_block_hash(&archive, &block_hash(4))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(payload.fork_name(), ForkName::Deneb);
+        assert_eq!(payload.as_ssz_bytes(), payloads[1].as_ssz_bytes());
+        assert_eq!(
+            PayloadArchive::<E>::get_by_slot(&archive, Slot::new(10))
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        PayloadArchive::<E>::prune(&archive, Slot::new(11))
+            .await
+            .unwrap();
+        assert!(
+            PayloadArchive::<E>::get_by_block_hash(&archive, &block_hash(3))
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(!dir.join("10").exists());
+        assert!(dir.join("11").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use types::{PublicKeyBytes, Uint256};
+
+/// A bid in an [`Auction`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bid<T> {
+    pub value: Uint256,
+    /// When the relay received the submission, in milliseconds since the Unix epoch.
+    pub received_at_ms: i64,
+    pub submission: T,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertOutcome {
+    /// The bid is now the builder's bid in this auction.
+    Accepted,
+    /// Cancellations are enabled and the builder already has a bid received after this one.
+    Outdated,
+    /// Cancellations are disabled and the builder already has a bid of equal or higher value.
+    NotHigher,
+}
+
+/// The bids competing for one (slot, parent_hash, proposer) auction.
+///
+/// Only one bid per builder takes part. Without cancellations a builder's bid is only replaced by
+/// a higher one, so a bid can never be withdrawn. With cancellations the most recently received
+/// bid replaces the builder's previous one even if its value is lower. "Most recent" is decided by
+/// receive time rather than processing order, so a submission that is processed after a later
+/// one from the same builder does not undo the cancellation.
+#[derive(Debug, Clone)]
+pub struct Auction<T> {
+    bids: HashMap<PublicKeyBytes, Bid<T>>,
+    top: Option<PublicKeyBytes>,
+}
+
+impl<T> Default for Auction<T> {
+    fn default() -> Self {
+        Self {
+            bids: HashMap::new(),
+            top: None,
+        }
+    }
+}
+
+impl<T> Auction<T> {
+    pub fn insert(
+        &mut self,
+        builder_pubkey: PublicKeyBytes,
+        bid: Bid<T>,
+        cancellations: bool,
+    ) -> InsertOutcome {
+        if let Some(previous) = self.bids.get(&builder_pubkey) {
+            if cancellations {
+                if previous.received_at_ms > bid.received_at_ms {
+                    return InsertOutcome::Outdated;
+                }
+            } else if bid.value <= previous.value {
+                return InsertOutcome::NotHigher;
+            }
+        }
+
+        self.bids.insert(builder_pubkey, bid);
+        self.recompute_top();
+        InsertOutcome::Accepted
+    }
+
+    /// The highest bid. Of bids with equal value the earliest received wins.
+    pub fn top(&self) -> Option<&Bid<T>> {
+        self.top.as_ref().and_then(|pubkey| self.bids.get(pubkey))
+    }
+
+    /// The current bid of `builder_pubkey`.
+    pub fn builder_bid(&self, builder_pubkey: &PublicKeyBytes) -> Option<&Bid<T>> {
+        self.bids.get(builder_pubkey)
+    }
+
+    /// Remove the bid of `builder_pubkey`, e.g. because its block turned out to be invalid.
+    pub fn remove(&mut self, builder_pubkey: &PublicKeyBytes) -> Option<Bid<T>> {
+        let bid = self.bids.remove(builder_pubkey)?;
+        self.recompute_top();
+        Some(bid)
+    }
+
+    fn recompute_top(&mut self) {
+        self.top = self
+            .bids
+            .iter()
+            .max_by(|(_, a), (_, b)| {
+                a.value
+                    .cmp(&b.value)
+                    .then(b.received_at_ms.cmp(&a.received_at_ms))
+            })
+            .map(|(pubkey, _)| *pubkey);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builder(i: u8) -> PublicKeyBytes {
+        PublicKeyBytes::deserialize(&[i; 48]).unwrap()
+    }
+
+    fn bid(value: u64, received_at_ms: i64) -> Bid<u64> {
+        Bid {
+            value: Uint256::from(value),
+            received_at_ms,
+            submission: value,
+        }
+    }
+
+    fn top_value(auction: &Auction<u64>) -> Option<u64> {
+        auction.top().map(|bid| bid.submission)
+    }
+
+    #[test]
+    fn lower_bid_replaces_with_cancellations() {
+        let mut auction = Auction::default();
+        assert_eq!(
+            auction.insert(builder(1), bid(10, 100), true),
+            InsertOutcome::Accepted
+        );
+        assert_eq!(
+            auction.insert(builder(1), bid(5, 200), true),
+            InsertOutcome::Accepted
+        );
+        assert_eq!(top_value(&auction), Some(5));
+    }
+
+    #[test]
+    fn lower_bid_ignored_without_cancellations() {
+        let mut auction = Auction::default();
+        auction.insert(builder(1), bid(10, 100), false);
+        assert_eq!(
+            auction.insert(builder(1), bid(5, 200), false),
+            InsertOutcome::NotHigher
+        );
+        assert_eq!(top_value(&auction), Some(10));
+
+        assert_eq!(
+            auction.insert(builder(1), bid(15, 300), false),
+            InsertOutcome::Accepted
+        );
+        assert_eq!(top_value(&auction), Some(15));
+    }
+
+    #[test]
+    fn cancellation_falls_back_to_other_builder() {
+        let mut auction = Auction::default();
+        auction.insert(builder(1), bid(10, 100), true);
+        auction.insert(builder(2), bid(8, 110), true);
+        assert_eq!(top_value(&auction), Some(10));
+
+        auction.insert(builder(1), bid(3, 120), true);
+        assert_eq!(top_value(&auction), Some(8));
+    }
+
+    #[test]
+    fn out_of_order_arrival_keeps_latest_received() {
+        let mut auction = Auction::default();
+        // The cancelling bid was received last but is processed first.
+        auction.insert(builder(1), bid(3, 200), true);
+        assert_eq!(
+            auction.insert(builder(1), bid(10, 100), true),
+            InsertOutcome::Outdated
+        );
+        assert_eq!(top_value(&auction), Some(3));
+    }
+
+    #[test]
+    fn out_of_order_arrival_without_cancellations() {
+        let mut auction = Auction::default();
+        // Without cancellations every bid stands, so the higher one wins regardless of order.
+        auction.insert(builder(1), bid(10, 200), false);
+        assert_eq!(
+            auction.insert(builder(1), bid(12, 100), false),
+            InsertOutcome::Accepted
+        );
+        assert_eq!(top_value(&auction), Some(12));
+    }
+
+    #[test]
+    fn equal_values_prefer_earliest_received() {
+        let mut auction = Auction::default();
+        auction.insert(builder(1), bid(10, 200), true);
+        auction.insert(builder(2), bid(10, 100), true);
+        assert_eq!(auction.top().map(|bid| bid.received_at_ms), Some(100));
+    }
+}
@@ -0,0 +1,184 @@
+use std::time::Duration;
+
+use axum::{
+    body::{self, Body},
+    extract::{Query, Request, State},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use http::{
+    header::{CACHE_CONTROL, CONTENT_LENGTH, CONTENT_TYPE, ETAG, IF_NONE_MATCH},
+    HeaderValue, StatusCode, Uri,
+};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tracing::error;
+use types::Slot;
+
+use crate::slot_clock::SlotClock;
+
+/// Default number of slots after which a slot is treated as finalized.
+pub const DEFAULT_FINALIZED_AFTER_SLOTS: u64 = 64;
+/// Default time shared caches may keep responses about finalized slots.
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(3600);
+
+fn no_store() -> HeaderValue {
+    HeaderValue::from_static("no-store")
+}
+
+/// Decides how long Data API responses may be cached.
+///
+/// Responses about finalized slots can no longer change, so they may be kept by shared caches.
+/// Everything else, including every query without a `slot` or `cursor`, is never cached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CachePolicy {
+    pub clock: SlotClock,
+    /// A slot this many slots before the current slot is treated as finalized.
+    pub finalized_after_slots: u64,
+    pub max_age: Duration,
+}
+
+#[derive(Deserialize)]
+struct SlotQuery {
+    slot: Option<Slot>,
+    cursor: Option<Slot>,
+}
+
+impl CachePolicy {
+    pub fn new(clock: SlotClock) -> Self {
+        Self {
+            clock,
+            finalized_after_slots: DEFAULT_FINALIZED_AFTER_SLOTS,
+            max_age: DEFAULT_MAX_AGE,
+        }
+    }
+
+    /// Whether `slot` is finalized.
+    pub fn is_finalized(&self, slot: Slot) -> bool {
+        self.clock
+            .now()
+            .is_some_and(|current_slot| slot + self.finalized_after_slots <= current_slot)
+    }
+
+    /// The `Cache-Control` header for a successful response to a request for `uri`.
+    fn cache_control(&self, uri: &Uri) -> HeaderValue {
+        let slot = Query::<SlotQuery>::try_from_uri(uri)
+            .ok()
+            .and_then(|Query(query)| query.slot.or(query.cursor));
+        match slot {
+            Some(slot) if self.is_finalized(slot) => {
+                HeaderValue::from_str(&format!("public, max-age={}", self.max_age.as_secs()))
+                    .unwrap_or_else(|_| no_store())
+            }
+            _ => no_store(),
+        }
+    }
+}
+
+/// Add `Cache-Control` and `ETag` headers to successful responses of `router`, answering
+/// requests with a matching `If-None-Match` header with `304 Not Modified`.
+pub fn with_cache_headers(router: Router, policy: CachePolicy) -> Router {
+    router.route_layer(middleware::from_fn_with_state(policy, cache_headers))
+}
+
+fn etag(body: &[u8]) -> HeaderValue {
+    let digest = Sha256::digest(body);
+    let tag = format!("\"{}\"", &serde_utils::hex::encode(&digest[..16])[2..]);
+    HeaderValue::from_str(&tag).unwrap_or(HeaderValue::from_static("\"\""))
+}
+
+/// Weak comparison as required for `If-None-Match`.
+fn etag_matches(if_none_match: &HeaderValue, etag: &HeaderValue) -> bool {
+    let Ok(if_none_match) = if_none_match.to_str() else {
+        return false;
+    };
+    let Ok(etag) = etag.to_str() else {
+        return false;
+    };
+    if_none_match.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+    })
+}
+
+async fn cache_headers(State(policy): State<CachePolicy>, req: Request, next: Next) -> Response {
+    let cache_control = policy.cache_control(req.uri());
+    let if_none_match = req.headers().get(IF_NONE_MATCH).cloned();
+
+    let response = next.run(req).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            error!(error = ?e, "Failed to buffer response body");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let etag = etag(&bytes);
+    parts.headers.insert(CACHE_CONTROL, cache_control);
+    parts.headers.insert(ETAG, etag.clone());
+
+    if if_none_match.is_some_and(|if_none_match| etag_matches(&if_none_match, &etag)) {
+        parts.status = StatusCode::NOT_MODIFIED;
+        parts.headers.remove(CONTENT_TYPE);
+        parts.headers.remove(CONTENT_LENGTH);
+        return Response::from_parts(parts, Body::empty());
+    }
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn if_none_match() {
+        let etag = etag(b"[]");
+        let matching = etag.to_str().unwrap().to_owned();
+
+        assert!(etag_matches(
+            &HeaderValue::from_str(&matching).unwrap(),
+            &etag
+        ));
+        assert!(etag_matches(
+            &HeaderValue::from_str(&format!("W/{matching}")).unwrap(),
+            &etag
+        ));
+        assert!(etag_matches(
+            &HeaderValue::from_str(&format!("\"other\", {matching}")).unwrap(),
+            &etag
+        ));
+        assert!(etag_matches(&HeaderValue::from_static("*"), &etag));
+        assert!(!etag_matches(&HeaderValue::from_static("\"other\""), &etag));
+    }
+
+    #[test]
+    fn only_finalized_slots_are_cacheable() {
+        let policy = CachePolicy::new(SlotClock::new(0, 12));
+        let current_slot = policy.clock.now().unwrap();
+        let finalized = current_slot - DEFAULT_FINALIZED_AFTER_SLOTS;
+
+        let uri = |query: String| format!("/relay/v1/data?{query}").parse::<Uri>().unwrap();
+        assert_eq!(
+            policy.cache_control(&uri(format!("slot={finalized}"))),
+            "public, max-age=3600"
+        );
+        assert_eq!(
+            policy.cache_control(&uri(format!("cursor={finalized}"))),
+            "public, max-age=3600"
+        );
+        assert_eq!(
+            policy.cache_control(&uri(format!("slot={current_slot}"))),
+            no_store()
+        );
+        assert_eq!(
+            policy.cache_control(&uri("block_number=1".into())),
+            no_store()
+        );
+    }
+}
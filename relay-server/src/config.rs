@@ -0,0 +1,514 @@
+use std::{
+    fmt, fs, io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::Deserialize;
+use types::{ChainSpec, Hash256};
+
+use crate::{
+    cache::{DEFAULT_FINALIZED_AFTER_SLOTS, DEFAULT_MAX_AGE},
+    dedup::DEFAULT_DUPLICATE_WINDOW,
+    duties::DEFAULT_BEACON_NODE_TIMEOUT,
+    simulator::DEFAULT_SIMULATION_TIMEOUT,
+    slot_clock::{DEFAULT_MAX_FUTURE_SLOTS, DEFAULT_SUBMISSION_CUTOFF},
+};
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Yaml(serde_yaml::Error),
+    UnknownFormat(PathBuf),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "unable to read config: {e}"),
+            Error::Toml(e) => write!(f, "invalid TOML config: {e}"),
+            Error::Yaml(e) => write!(f, "invalid YAML config: {e}"),
+            Error::UnknownFormat(path) => write!(
+                f,
+                "unknown config format of {}, expected .toml, .yaml or .yml",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for Error {
+    fn from(e: toml::de::Error) -> Self {
+        Error::Toml(e)
+    }
+}
+
+impl From<serde_yaml::Error> for Error {
+    fn from(e: serde_yaml::Error) -> Self {
+        Error::Yaml(e)
+    }
+}
+
+/// Configuration of a standalone relay process.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub listen_address: SocketAddr,
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    #[serde(default)]
+    pub network: Network,
+    /// Genesis time in seconds since the Unix epoch. Defaults to the network preset's genesis
+    /// time, if it has one.
+    #[serde(default)]
+    pub genesis_time: Option<u64>,
+    /// Genesis validators root, which the signatures of proposers' blinded blocks commit to.
+    /// Defaults to the network preset's, if it has one.
+    #[serde(default)]
+    pub genesis_validators_root: Option<Hash256>,
+    /// Hex-encoded BLS secret key signing the bids served to proposers. Without one headers are
+    /// not served and blinded blocks not accepted. Needs a genesis validators root.
+    #[serde(default)]
+    pub signing_key: Option<String>,
+    #[serde(default)]
+    pub limits: Limits,
+    #[serde(default)]
+    pub backend: Backend,
+    #[serde(default)]
+    pub storage: StorageConfig,
+    /// Execution client used to simulate submissions before accepting them. Needs a beacon
+    /// node, which provides the parent beacon block roots Deneb and later simulations need.
+    #[serde(default)]
+    pub simulator: Option<SimulatorConfig>,
+    /// Beacon node to fetch proposer duties from, which `getValidators` serves, and the head
+    /// block submissions are checked against. Needs a genesis time.
+    #[serde(default)]
+    pub beacon_node: Option<BeaconNodeConfig>,
+    /// Serve the Admin API, authenticated with this bearer token. Builders then need an API key
+    /// issued through the Admin API to submit blocks.
+    #[serde(default)]
+    pub admin_token: Option<String>,
+    /// Serve Prometheus metrics on `/metrics`.
+    #[serde(default)]
+    pub metrics: bool,
+    /// Allow browsers on other origins to query the Data API.
+    #[serde(default)]
+    pub cors: Option<CorsConfig>,
+    /// Add `Cache-Control` and `ETag` headers to Data API responses. Needs a genesis time.
+    #[serde(default)]
+    pub cache: Option<CacheConfig>,
+}
+
+impl Config {
+    pub fn genesis_time(&self) -> Option<u64> {
+        self.genesis_time.or(self.network.genesis_time())
+    }
+
+    pub fn genesis_validators_root(&self) -> Option<Hash256> {
+        self.genesis_validators_root
+            .or(self.network.genesis_validators_root())
+    }
+
+    /// Load a config file, picking the format from the `.toml`, `.yaml` or `.yml` extension.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path)?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Ok(toml::from_str(&contents)?),
+            Some("yaml" | "yml") => Ok(serde_yaml::from_str(&contents)?),
+            _ => Err(Error::UnknownFormat(path.to_path_buf())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    /// PEM certificate chain, reloaded on SIGHUP along with the key.
+    pub cert_path: PathBuf,
+    /// PEM private key.
+    pub key_path: PathBuf,
+    /// Offer HTTP/2 to clients through ALPN.
+    #[serde(default)]
+    pub http2: bool,
+}
+
+/// Where the Data API's received bids, delivered payloads and validator registrations are kept.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    /// Directory of the storage log. Data is kept in memory only if unset.
+    pub path: Option<PathBuf>,
+    /// Directory of the payload archive, which keeps the full submission of every delivered
+    /// payload. Payloads are not archived if unset.
+    pub archive_path: Option<PathBuf>,
+    /// Number of slots of received bids, delivered payloads and archived payloads to keep. Kept
+    /// forever if unset.
+    pub retention_slots: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CorsConfig {
+    /// Origins allowed to make requests, or `*` for any origin.
+    pub allowed_origins: Vec<String>,
+    /// How long browsers may cache preflight responses.
+    #[serde(default = "default_cors_max_age_secs")]
+    pub max_age_secs: u64,
+}
+
+fn default_cors_max_age_secs() -> u64 {
+    3600
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    /// A slot this many slots before the current slot is treated as finalized, making responses
+    /// about it cacheable.
+    pub finalized_after_slots: u64,
+    /// How long shared caches may keep responses about finalized slots.
+    pub max_age_secs: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            finalized_after_slots: DEFAULT_FINALIZED_AFTER_SLOTS,
+            max_age_secs: DEFAULT_MAX_AGE.as_secs(),
+        }
+    }
+}
+
+impl CacheConfig {
+    pub fn max_age(&self) -> Duration {
+        Duration::from_secs(self.max_age_secs)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SimulatorConfig {
+    /// JSON-RPC endpoint serving `flashbots_validateBuilderSubmissionV*`.
+    pub url: String,
+    #[serde(default = "default_simulation_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_simulation_timeout_ms() -> u64 {
+    DEFAULT_SIMULATION_TIMEOUT.as_millis() as u64
+}
+
+impl SimulatorConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BeaconNodeConfig {
+    /// Base URL of the beacon node's HTTP API.
+    pub url: String,
+    #[serde(default = "default_beacon_node_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_beacon_node_timeout_ms() -> u64 {
+    DEFAULT_BEACON_NODE_TIMEOUT.as_millis() as u64
+}
+
+impl BeaconNodeConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+/// Network preset, which selects the `EthSpec` and `ChainSpec` the relay runs with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    #[default]
+    Mainnet,
+    Gnosis,
+    Minimal,
+}
+
+impl Network {
+    pub fn chain_spec(&self) -> ChainSpec {
+        match self {
+            Network::Mainnet => ChainSpec::mainnet(),
+            Network::Gnosis => ChainSpec::gnosis(),
+            Network::Minimal => ChainSpec::minimal(),
+        }
+    }
+
+    pub fn genesis_time(&self) -> Option<u64> {
+        match self {
+            Network::Mainnet => Some(1606824023),
+            Network::Gnosis => Some(1638993340),
+            Network::Minimal => None,
+        }
+    }
+
+    pub fn genesis_validators_root(&self) -> Option<Hash256> {
+        let root = match self {
+            Network::Mainnet => {
+                "0x4b363db94e286120d76eb905340fdd4e54bfe9f06bf33ff6cf5ad27f511bfe95"
+            }
+            Network::Gnosis => "0xf5dcb5564e829aab27264b9becd5dfaa017085611224cb3036f573368dbb9d47",
+            Network::Minimal => return None,
+        };
+        root.parse().ok()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Limits {
+    /// Largest accepted request body.
+    pub max_body_bytes: usize,
+    /// Requests handled concurrently before new ones wait for a free slot.
+    pub max_concurrent_requests: usize,
+    pub request_timeout_ms: u64,
+    /// How long in-flight requests are given to complete on shutdown.
+    pub shutdown_timeout_ms: u64,
+    /// Number of slots ahead of the current slot a submission may be for.
+    pub max_future_slots: u64,
+    /// Submissions for the current slot are rejected this long after it started.
+    pub submission_cutoff_ms: u64,
+    /// How long an accepted submission is remembered to acknowledge resubmissions of it.
+    pub duplicate_window_ms: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: 10 * 1024 * 1024,
+            max_concurrent_requests: 1024,
+            request_timeout_ms: 5_000,
+            shutdown_timeout_ms: 10_000,
+            max_future_slots: DEFAULT_MAX_FUTURE_SLOTS,
+            submission_cutoff_ms: DEFAULT_SUBMISSION_CUTOFF.as_millis() as u64,
+            duplicate_window_ms: DEFAULT_DUPLICATE_WINDOW.as_millis() as u64,
+        }
+    }
+}
+
+impl Limits {
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_millis(self.request_timeout_ms)
+    }
+
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_millis(self.shutdown_timeout_ms)
+    }
+
+    pub fn submission_cutoff(&self) -> Duration {
+        Duration::from_millis(self.submission_cutoff_ms)
+    }
+
+    pub fn duplicate_window(&self) -> Duration {
+        Duration::from_millis(self.duplicate_window_ms)
+    }
+}
+
+/// The implementation serving the relay APIs.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Backend {
+    /// Serve the routes but answer every request with `503 Service Unavailable`. Useful to check
+    /// a deployment before a relay implementation is wired in.
+    #[default]
+    Unavailable,
+    /// Serve the APIs from an [`InMemoryRelay`](crate::in_memory::InMemoryRelay). All state is
+    /// lost on shutdown.
+    InMemory,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML: &str = r#"
+listen_address = "0.0.0.0:9062"
+network = "minimal"
+genesis_time = 100
+metrics = true
+
+[backend]
+type = "in_memory"
+
+[limits]
+max_body_bytes = 1024
+
+[storage]
+path = "/var/lib/relay"
+retention_slots = 64
+
+[simulator]
+url = "http://localhost:8545"
+
+[beacon_node]
+url = "http://localhost:5052"
+timeout_ms = 1000
+
+[cors]
+allowed_origins = ["*"]
+
+[cache]
+max_age_secs = 60
+"#;
+
+    const YAML: &str = r#"
+listen_address: 0.0.0.0:9062
+network: minimal
+genesis_time: 100
+metrics: true
+backend:
+  type: in_memory
+limits:
+  max_body_bytes: 1024
+storage:
+  path: /var/lib/relay
+  retention_slots: 64
+simulator:
+  url: http://localhost:8545
+beacon_node:
+  url: http://localhost:5052
+  timeout_ms: 1000
+cors:
+  allowed_origins: ["*"]
+cache:
+  max_age_secs: 60
+"#;
+
+    /// A fresh directory under the system's temporary directory.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "relay-config-{name}-{}-{}",
+            std::process::id(),
+            rand::random::<u64>()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn defaults() {
+        let config: Config = toml::from_str(r#"listen_address = "127.0.0.1:18550""#).unwrap();
+        assert_eq!(config.network, Network::Mainnet);
+        assert_eq!(config.backend, Backend::Unavailable);
+        assert_eq!(config.limits, Limits::default());
+        assert_eq!(config.storage, StorageConfig::default());
+        assert_eq!(config.tls, None);
+        assert_eq!(config.simulator, None);
+        assert_eq!(config.beacon_node, None);
+        assert_eq!(config.cache, None);
+        assert!(!config.metrics);
+        assert_eq!(config.genesis_time(), Some(1606824023));
+        assert_eq!(
+            config.genesis_validators_root(),
+            Network::Mainnet.genesis_validators_root()
+        );
+        assert!(config.genesis_validators_root().is_some());
+    }
+
+    #[test]
+    fn toml_and_yaml() {
+        let config: Config = toml::from_str(TOML).unwrap();
+        assert_eq!(serde_yaml::from_str::<Config>(YAML).unwrap(), config);
+
+        assert_eq!(config.network, Network::Minimal);
+        assert_eq!(config.genesis_time(), Some(100));
+        assert_eq!(config.genesis_validators_root(), None);
+        assert_eq!(config.backend, Backend::InMemory);
+        assert!(config.metrics);
+        // Unset fields of a section keep their defaults.
+        assert_eq!(
+            config.limits,
+            Limits {
+                max_body_bytes: 1024,
+                ..Limits::default()
+            }
+        );
+        assert_eq!(config.storage.retention_slots, Some(64));
+        assert_eq!(config.storage.archive_path, None);
+        let simulator = config.simulator.unwrap();
+        assert_eq!(simulator.timeout(), DEFAULT_SIMULATION_TIMEOUT);
+        assert_eq!(
+            config.beacon_node.unwrap().timeout(),
+            Duration::from_secs(1)
+        );
+        assert_eq!(config.cors.unwrap().max_age_secs, 3600);
+        let cache = config.cache.unwrap();
+        assert_eq!(cache.max_age(), Duration::from_secs(60));
+        assert_eq!(cache.finalized_after_slots, DEFAULT_FINALIZED_AFTER_SLOTS);
+    }
+
+    #[test]
+    fn unknown_fields_rejected() {
+        for toml in [
+            "listen_adress = \"127.0.0.1:18550\"",
+            "listen_address = \"127.0.0.1:18550\"\n[limits]\nmax_body = 1",
+            "listen_address = \"127.0.0.1:18550\"\n[backend]\ntype = \"postgres\"",
+            "listen_address = \"127.0.0.1:18550\"\n[simulator]\nurl = \"x\"\ntimeout = 1",
+        ] {
+            assert!(toml::from_str::<Config>(toml).is_err(), "{toml}");
+        }
+        for yaml in [
+            "listen_address: 127.0.0.1:18550\nnetwork: holesky",
+            "listen_address: 127.0.0.1:18550\nstorage:\n  retention: 1",
+        ] {
+            assert!(serde_yaml::from_str::<Config>(yaml).is_err(), "{yaml}");
+        }
+    }
+
+    #[test]
+    fn load() {
+        let dir = temp_dir("load");
+        for (name, contents) in [
+            ("relay.toml", TOML),
+            ("relay.yaml", YAML),
+            ("relay.yml", YAML),
+        ] {
+            fs::write(dir.join(name), contents).unwrap();
+            assert_eq!(
+                Config::load(&dir.join(name)).unwrap(),
+                toml::from_str(TOML).unwrap()
+            );
+        }
+
+        let path = dir.join("relay.json");
+        fs::write(&path, "{}").unwrap();
+        let e = Config::load(&path).unwrap_err();
+        assert!(matches!(&e, Error::UnknownFormat(unknown) if *unknown == path));
+        assert_eq!(
+            e.to_string(),
+            format!(
+                "unknown config format of {}, expected .toml, .yaml or .yml",
+                path.display()
+            )
+        );
+        assert!(matches!(
+            Config::load(&dir.join("missing.toml")),
+            Err(Error::Io(_))
+        ));
+        fs::write(dir.join("invalid.toml"), "listen_address = 1").unwrap();
+        assert!(Config::load(&dir.join("invalid.toml"))
+            .unwrap_err()
+            .to_string()
+            .starts_with("invalid TOML config"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
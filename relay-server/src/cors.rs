@@ -0,0 +1,32 @@
+use std::time::Duration;
+
+use http::{
+    header::{InvalidHeaderValue, ETAG, IF_NONE_MATCH},
+    HeaderValue, Method,
+};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::config::CorsConfig;
+
+/// Build the CORS policy for the Data API. Browsers may only issue `GET` requests, and may read
+/// the `ETag` header to revalidate cached responses.
+pub fn cors_layer(config: &CorsConfig) -> Result<CorsLayer, InvalidHeaderValue> {
+    let allow_origin = if config.allowed_origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            config
+                .allowed_origins
+                .iter()
+                .map(|origin| HeaderValue::from_str(origin))
+                .collect::<Result<Vec<_>, _>>()?,
+        )
+    };
+
+    Ok(CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET])
+        .allow_headers([IF_NONE_MATCH])
+        .expose_headers([ETAG])
+        .max_age(Duration::from_secs(config.max_age_secs)))
+}
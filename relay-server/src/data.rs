@@ -1,7 +1,9 @@
 use async_trait::async_trait;
 use relay_api_types::{
-    GetDeliveredPayloadsQueryParams, GetDeliveredPayloadsResponse, GetReceivedBidsQueryParams,
-    GetReceivedBidsResponse, GetValidatorRegistrationQueryParams, GetValidatorRegistrationResponse,
+    ErrorResponse, GetBuilderDemotionsQueryParams, GetBuilderDemotionsResponse,
+    GetDeliveredPayloadsQueryParams, GetDeliveredPayloadsResponse, GetReceivedBidTracesResponse,
+    GetReceivedBidsQueryParams, GetReceivedBidsResponse, GetValidatorRegistrationQueryParams,
+    GetValidatorRegistrationResponse, Response,
 };
 
 /// Data
@@ -24,6 +26,17 @@ pub trait Data {
         query_params: GetReceivedBidsQueryParams,
     ) -> GetReceivedBidsResponse;
 
+    /// Like [`Data::get_received_bids`], including when the relay received each bid. `None` if
+    /// the relay does not track it, in which case `get_received_bids` is served instead.
+    ///
+    /// GetReceivedBids - GET /relay/v1/data/bidtraces/builder_blocks_received
+    async fn get_received_bid_traces(
+        &self,
+        _query_params: GetReceivedBidsQueryParams,
+    ) -> Option<GetReceivedBidTracesResponse> {
+        None
+    }
+
     /// Check that a validator is registered with the relay..
     ///
     /// GetValidatorRegistration - GET /relay/v1/data/validator_registration
@@ -31,4 +44,18 @@ pub trait Data {
         &self,
         query_params: GetValidatorRegistrationQueryParams,
     ) -> GetValidatorRegistrationResponse;
+
+    /// Get demotions of optimistic builders whose blocks failed simulation..
+    ///
+    /// GetBuilderDemotions - GET /relay/v1/data/builder_demotions
+    async fn get_builder_demotions(
+        &self,
+        _query_params: GetBuilderDemotionsQueryParams,
+    ) -> GetBuilderDemotionsResponse {
+        Response::Error(ErrorResponse {
+            code: 501,
+            message: "builder demotions are not tracked by this relay".to_string(),
+            stacktraces: None,
+        })
+    }
 }
@@ -0,0 +1,157 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use relay_api_types::BidTraceV1;
+use types::{ExecutionBlockHash, PublicKeyBytes, Slot};
+
+/// Default time a submission is remembered for.
+pub const DEFAULT_DUPLICATE_WINDOW: Duration = Duration::from_secs(12);
+
+/// Identifies a submission for duplicate detection.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SubmissionKey {
+    pub slot: Slot,
+    pub builder_pubkey: PublicKeyBytes,
+    pub block_hash: ExecutionBlockHash,
+}
+
+impl From<&BidTraceV1> for SubmissionKey {
+    fn from(trace: &BidTraceV1) -> Self {
+        Self {
+            slot: trace.slot,
+            builder_pubkey: trace.builder_pubkey,
+            block_hash: trace.block_hash,
+        }
+    }
+}
+
+/// Remembers accepted submissions so that resubmissions can be acknowledged without processing
+/// them again.
+///
+/// Implementations backed by a shared store let relay instances behind a load balancer
+/// recognise submissions accepted by each other.
+#[async_trait]
+pub trait DuplicateStore: Send + Sync {
+    /// Record the submission with `key` unless one was recorded within the store's window.
+    /// Returns whether it was recorded. Of concurrent calls with the same key at most one
+    /// records it.
+    async fn insert_if_absent(&self, key: SubmissionKey) -> bool;
+
+    /// Forget the submission with `key`, e.g. because it was rejected after all.
+    async fn remove(&self, key: &SubmissionKey);
+}
+
+/// A [`DuplicateStore`] local to one process.
+pub struct InMemoryDuplicateStore {
+    window: Duration,
+    seen: Mutex<Seen>,
+}
+
+#[derive(Default)]
+struct Seen {
+    keys: HashMap<SubmissionKey, Instant>,
+    /// Keys in insertion order, for expiry.
+    order: VecDeque<(Instant, SubmissionKey)>,
+}
+
+impl Seen {
+    fn prune(&mut self, now: Instant, window: Duration) {
+        while let Some((inserted, _)) = self.order.front() {
+            if now.duration_since(*inserted) < window {
+                break;
+            }
+            if let Some((inserted, key)) = self.order.pop_front() {
+                // Only forget the key if it was not inserted again since.
+                if self.keys.get(&key) == Some(&inserted) {
+                    self.keys.remove(&key);
+                }
+            }
+        }
+    }
+}
+
+impl InMemoryDuplicateStore {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            seen: Mutex::new(Seen::default()),
+        }
+    }
+}
+
+impl Default for InMemoryDuplicateStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_DUPLICATE_WINDOW)
+    }
+}
+
+#[async_trait]
+impl DuplicateStore for InMemoryDuplicateStore {
+    async fn insert_if_absent(&self, key: SubmissionKey) -> bool {
+        let now = Instant::now();
+        let mut seen = self.seen.lock();
+        seen.prune(now, self.window);
+        if seen.keys.contains_key(&key) {
+            return false;
+        }
+        seen.keys.insert(key.clone(), now);
+        seen.order.push_back((now, key));
+        true
+    }
+
+    async fn remove(&self, key: &SubmissionKey) {
+        // The entry in `order` is skipped on expiry as the key is gone.
+        self.seen.lock().keys.remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::test_utils::trace;
+
+    #[tokio::test]
+    async fn insert_and_remove() {
+        let store = InMemoryDuplicateStore::default();
+        let key = SubmissionKey::from(&trace(10, 1, 2, 3, 100));
+
+        assert!(store.insert_if_absent(key.clone()).await);
+        assert!(!store.insert_if_absent(key.clone()).await);
+        store.remove(&key).await;
+        assert!(store.insert_if_absent(key).await);
+    }
+
+    #[tokio::test]
+    async fn expiry() {
+        let store = InMemoryDuplicateStore::new(Duration::from_millis(10));
+        let key = SubmissionKey::from(&trace(10, 1, 2, 3, 100));
+
+        assert!(store.insert_if_absent(key.clone()).await);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(store.insert_if_absent(key).await);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_duplicates() {
+        let store = Arc::new(InMemoryDuplicateStore::default());
+        let key = SubmissionKey::from(&trace(10, 1, 2, 3, 100));
+
+        let inserts = (0..16)
+            .map(|_| {
+                let (store, key) = (store.clone(), key.clone());
+                tokio::spawn(async move { store.insert_if_absent(key).await })
+            })
+            .collect::<Vec<_>>();
+        let mut inserted = 0;
+        for insert in inserts {
+            inserted += usize::from(insert.await.unwrap());
+        }
+        assert_eq!(inserted, 1);
+    }
+}
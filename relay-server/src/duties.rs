@@ -0,0 +1,330 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use beacon_client::BeaconNodeHttpClient;
+use parking_lot::RwLock;
+use relay_api_types::ValidatorsResponse;
+use tracing::{debug, warn};
+use types::{Epoch, EthSpec, PublicKeyBytes, SignedValidatorRegistrationData, Slot};
+
+use crate::{
+    slot_clock::SlotClock,
+    storage::{self, Storage},
+};
+
+/// Default timeout of beacon node requests.
+pub const DEFAULT_BEACON_NODE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug)]
+pub enum Error {
+    BeaconNode(beacon_client::Error),
+    Storage(storage::Error),
+}
+
+impl From<beacon_client::Error> for Error {
+    fn from(e: beacon_client::Error) -> Self {
+        Error::BeaconNode(e)
+    }
+}
+
+impl From<storage::Error> for Error {
+    fn from(e: storage::Error) -> Self {
+        Error::Storage(e)
+    }
+}
+
+/// A validator scheduled to propose at `slot`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProposerDuty {
+    pub slot: Slot,
+    pub validator_index: u64,
+    pub pubkey: PublicKeyBytes,
+}
+
+/// Where proposer duties come from, usually a beacon node.
+#[async_trait]
+pub trait DutiesProvider: Send + Sync {
+    async fn proposer_duties(&self, epoch: Epoch) -> Result<Vec<ProposerDuty>, Error>;
+}
+
+#[async_trait]
+impl DutiesProvider for BeaconNodeHttpClient {
+    async fn proposer_duties(&self, epoch: Epoch) -> Result<Vec<ProposerDuty>, Error> {
+        let response = self.get_validator_duties_proposer(epoch).await?;
+        Ok(response
+            .data
+            .into_iter()
+            .map(|duty| ProposerDuty {
+                slot: duty.slot,
+                validator_index: duty.validator_index,
+                pubkey: duty.pubkey,
+            })
+            .collect())
+    }
+}
+
+/// Keeps the latest registration of every validator and the upcoming proposer duties, and
+/// joins them into the entries served by `get_validators`.
+#[derive(Default)]
+pub struct ProposerSchedule {
+    registrations: RwLock<HashMap<PublicKeyBytes, SignedValidatorRegistrationData>>,
+    duties: RwLock<BTreeMap<Slot, ProposerDuty>>,
+}
+
+impl ProposerSchedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep `registration` unless a newer one is kept for the same validator. Returns whether
+    /// it was kept.
+    pub fn insert_registration(&self, registration: SignedValidatorRegistrationData) -> bool {
+        let mut registrations = self.registrations.write();
+        let pubkey = registration.message.pubkey;
+        if registrations
+            .get(&pubkey)
+            .is_some_and(|kept| kept.message.timestamp > registration.message.timestamp)
+        {
+            return false;
+        }
+        registrations.insert(pubkey, registration);
+        true
+    }
+
+    pub fn registration(&self, pubkey: &PublicKeyBytes) -> Option<SignedValidatorRegistrationData> {
+        self.registrations.read().get(pubkey).cloned()
+    }
+
+    pub fn duty(&self, slot: Slot) -> Option<ProposerDuty> {
+        self.duties.read().get(&slot).copied()
+    }
+
+    pub fn set_duty(&self, duty: ProposerDuty) {
+        self.duties.write().insert(duty.slot, duty);
+    }
+
+    /// Replace all duties from `slot` on with `duties`.
+    pub fn replace_duties(&self, slot: Slot, duties: Vec<ProposerDuty>) {
+        let mut kept = self.duties.write();
+        kept.retain(|kept_slot, _| *kept_slot < slot);
+        kept.extend(duties.into_iter().map(|duty| (duty.slot, duty)));
+    }
+
+    /// Drop duties for slots before `slot`. Registrations are kept.
+    pub fn prune(&self, slot: Slot) {
+        let mut duties = self.duties.write();
+        *duties = duties.split_off(&slot);
+    }
+
+    /// Upcoming proposers with their registrations. Proposers that did not register are left
+    /// out.
+    pub fn validators(&self) -> Vec<ValidatorsResponse> {
+        let duties = self.duties.read();
+        let registrations = self.registrations.read();
+        duties
+            .values()
+            .filter_map(|duty| {
+                Some(ValidatorsResponse {
+                    slot: duty.slot,
+                    validator_index: duty.validator_index,
+                    entry: registrations.get(&duty.pubkey)?.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Fetch the duties of `epoch` and the next epoch from `provider`, replacing older ones.
+    /// If only the next epoch cannot be fetched, the duties of `epoch` are still replaced and
+    /// those kept for the next epoch stay. Registrations of scheduled proposers not kept yet,
+    /// e.g. after a restart, are loaded from `storage`.
+    pub async fn refresh<E: EthSpec>(
+        &self,
+        provider: &dyn DutiesProvider,
+        storage: &dyn Storage,
+        epoch: Epoch,
+    ) -> Result<(), Error> {
+        let mut duties = provider.proposer_duties(epoch).await?;
+        let next_epoch = epoch + 1;
+        match provider.proposer_duties(next_epoch).await {
+            Ok(next_duties) => duties.extend(next_duties),
+            Err(e) => {
+                warn!(epoch = %next_epoch, error = ?e, "Failed to fetch proposer duties");
+                let next_start_slot = next_epoch.start_slot(E::slots_per_epoch());
+                duties.extend(
+                    self.duties
+                        .read()
+                        .range(next_start_slot..)
+                        .map(|(_, duty)| *duty),
+                );
+            }
+        }
+
+        for duty in &duties {
+            if self.registrations.read().contains_key(&duty.pubkey) {
+                continue;
+            }
+            if let Some(registration) = storage.registration(&duty.pubkey).await? {
+                self.insert_registration(registration);
+            }
+        }
+
+        let start_slot = epoch.start_slot(E::slots_per_epoch());
+        self.prune(start_slot);
+        self.replace_duties(start_slot, duties);
+        Ok(())
+    }
+}
+
+/// Refresh `schedule` at the start of every epoch, retrying every slot on failure.
+pub async fn refresh_every_epoch<E: EthSpec>(
+    schedule: Arc<ProposerSchedule>,
+    provider: Arc<dyn DutiesProvider>,
+    storage: Arc<dyn Storage>,
+    clock: SlotClock,
+) {
+    loop {
+        let Some(current_slot) = clock.now() else {
+            let until_genesis = clock
+                .start_of(Slot::new(0))
+                .saturating_sub(SlotClock::now_duration());
+            tokio::time::sleep(until_genesis).await;
+            continue;
+        };
+        let epoch = current_slot.epoch(E::slots_per_epoch());
+
+        let next_refresh = match schedule
+            .refresh::<E>(provider.as_ref(), storage.as_ref(), epoch)
+            .await
+        {
+            Ok(()) => {
+                debug!(epoch = %epoch, "Refreshed proposer duties");
+                clock.start_of((epoch + 1).start_slot(E::slots_per_epoch()))
+            }
+            Err(e) => {
+                warn!(epoch = %epoch, error = ?e, "Failed to refresh proposer duties");
+                clock.start_of(current_slot + 1)
+            }
+        };
+        tokio::time::sleep(next_refresh.saturating_sub(SlotClock::now_duration())).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use beacon_client::StatusCode;
+    use types::MainnetEthSpec;
+
+    use super::*;
+    use crate::{
+        storage::MemoryStorage,
+        test_utils::{pubkey, registration},
+    };
+
+    type E = MainnetEthSpec;
+
+    /// Serves the duties of the epochs it has and fails for all others.
+    struct MockProvider(HashMap<Epoch, Vec<ProposerDuty>>);
+
+    #[async_trait]
+    impl DutiesProvider for MockProvider {
+        async fn proposer_duties(&self, epoch: Epoch) -> Result<Vec<ProposerDuty>, Error> {
+            self.0
+                .get(&epoch)
+                .cloned()
+                .ok_or(Error::BeaconNode(beacon_client::Error::StatusCode(
+                    StatusCode::NOT_FOUND,
+                )))
+        }
+    }
+
+    fn duty(slot: u64, byte: u8) -> ProposerDuty {
+        ProposerDuty {
+            slot: Slot::new(slot),
+            validator_index: byte as u64,
+            pubkey: pubkey(byte),
+        }
+    }
+
+    fn registration_at(proposer: u8, timestamp: u64) -> SignedValidatorRegistrationData {
+        let mut registration = registration(proposer);
+        registration.message.timestamp = timestamp;
+        registration
+    }
+
+    #[test]
+    fn joins_duties_with_latest_registrations() {
+        let schedule = ProposerSchedule::new();
+        assert!(schedule.insert_registration(registration_at(1, 2)));
+        assert!(!schedule.insert_registration(registration_at(1, 1)));
+        // Only validator 1 registered.
+        schedule.set_duty(duty(1, 1));
+        schedule.set_duty(duty(2, 2));
+
+        let validators = schedule.validators();
+        assert_eq!(validators.len(), 1);
+        assert_eq!(validators[0].slot, Slot::new(1));
+        assert_eq!(validators[0].entry.message.timestamp, 2);
+
+        schedule.prune(Slot::new(2));
+        assert!(schedule.validators().is_empty());
+    }
+
+    #[tokio::test]
+    async fn refresh() {
+        let schedule = ProposerSchedule::new();
+        let storage = MemoryStorage::new();
+        storage
+            .insert_registration(registration_at(2, 1))
+            .await
+            .unwrap();
+        schedule.set_duty(duty(31, 1));
+        schedule.set_duty(duty(40, 1));
+        let provider = MockProvider(HashMap::from([
+            (Epoch::new(1), vec![duty(33, 2)]),
+            (Epoch::new(2), vec![duty(64, 3)]),
+        ]));
+
+        schedule
+            .refresh::<E>(&provider, &storage, Epoch::new(1))
+            .await
+            .unwrap();
+
+        // Older and outdated duties are dropped, and the registration of the new proposer is
+        // loaded from storage.
+        assert_eq!(schedule.duty(Slot::new(31)), None);
+        assert_eq!(schedule.duty(Slot::new(40)), None);
+        assert_eq!(schedule.duty(Slot::new(64)), Some(duty(64, 3)));
+        let validators = schedule.validators();
+        assert_eq!(validators.len(), 1);
+        assert_eq!(validators[0].slot, Slot::new(33));
+        assert_eq!(validators[0].entry, registration_at(2, 1));
+    }
+
+    #[tokio::test]
+    async fn refresh_without_next_epoch() {
+        let schedule = ProposerSchedule::new();
+        let storage = MemoryStorage::new();
+        schedule.set_duty(duty(40, 1));
+        schedule.set_duty(duty(64, 1));
+        let provider = MockProvider(HashMap::from([(Epoch::new(1), vec![duty(33, 2)])]));
+
+        schedule
+            .refresh::<E>(&provider, &storage, Epoch::new(1))
+            .await
+            .unwrap();
+        assert_eq!(schedule.duty(Slot::new(33)), Some(duty(33, 2)));
+        assert_eq!(schedule.duty(Slot::new(40)), None);
+        assert_eq!(schedule.duty(Slot::new(64)), Some(duty(64, 1)));
+
+        // Without the current epoch nothing is replaced.
+        assert!(schedule
+            .refresh::<E>(&provider, &storage, Epoch::new(3))
+            .await
+            .is_err());
+        assert_eq!(schedule.duty(Slot::new(33)), Some(duty(33, 2)));
+    }
+}
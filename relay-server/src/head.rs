@@ -0,0 +1,121 @@
+use std::{cmp, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use beacon_client::{types::BlockId, BeaconNodeHttpClient};
+use tracing::warn;
+use types::{EthSpec, ExecPayload, ExecutionBlockHash, Hash256, Slot};
+
+use crate::{in_memory::InMemoryRelay, slot_clock::SlotClock};
+
+/// Default time between polls of the beacon node's head.
+pub const DEFAULT_HEAD_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The head block of the chain.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Head {
+    pub slot: Slot,
+    pub root: Hash256,
+    /// The head's execution block, `None` before the merge.
+    pub execution: Option<ExecutionHead>,
+}
+
+/// The execution block of the head block of the chain.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExecutionHead {
+    pub block_hash: ExecutionBlockHash,
+    pub gas_limit: u64,
+}
+
+/// Where the head of the chain comes from, usually a beacon node.
+#[async_trait]
+pub trait HeadProvider<E: EthSpec>: Send + Sync {
+    /// Returns `None` if the beacon node has no head block yet.
+    async fn head(&self) -> Result<Option<Head>, beacon_client::Error>;
+}
+
+#[async_trait]
+impl<E: EthSpec> HeadProvider<E> for BeaconNodeHttpClient {
+    async fn head(&self) -> Result<Option<Head>, beacon_client::Error> {
+        let Some(response) = self.get_beacon_blocks::<E>(BlockId::Head).await? else {
+            return Ok(None);
+        };
+        let block = response.data;
+        let execution = block
+            .message()
+            .execution_payload()
+            .ok()
+            .map(|payload| ExecutionHead {
+                block_hash: payload.block_hash(),
+                gas_limit: payload.gas_limit(),
+            });
+        Ok(Some(Head {
+            slot: block.slot(),
+            root: block.canonical_root(),
+            execution,
+        }))
+    }
+}
+
+/// Slots whose block builds on `head` as of `current_slot`: the slots after the head, at most
+/// the current and the next one.
+pub fn slots_building_on(head: Head, current_slot: Slot) -> impl Iterator<Item = Slot> {
+    let first = cmp::max(head.slot + 1, current_slot);
+    (first.as_u64()..=current_slot.as_u64() + 1).map(Slot::new)
+}
+
+/// Keep the parent blocks `relay` validates submissions against up to date by polling
+/// `provider` for the head every `interval`. Deneb and later simulations need the parent beacon
+/// block root, and the gas limit of a block is bounded by its parent's.
+pub async fn track_head<E: EthSpec>(
+    relay: InMemoryRelay<E>,
+    provider: Arc<dyn HeadProvider<E>>,
+    clock: SlotClock,
+    interval: Duration,
+) {
+    let mut interval = tokio::time::interval(interval);
+    loop {
+        interval.tick().await;
+        let Some(current_slot) = clock.now() else {
+            continue;
+        };
+        match provider.head().await {
+            Ok(Some(head)) => {
+                for slot in slots_building_on(head, current_slot) {
+                    relay.set_parent_beacon_block_root(slot, head.root);
+                    if let Some(execution) = head.execution {
+                        relay.set_parent_gas_limit(slot, execution.block_hash, execution.gas_limit);
+                    }
+                }
+            }
+            Ok(None) => warn!("Beacon node has no head block"),
+            Err(e) => warn!(error = ?e, "Failed to get head block"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slots_after_head() {
+        let head = |slot| Head {
+            slot: Slot::new(slot),
+            root: Hash256::zero(),
+            execution: None,
+        };
+        let slots = |head, current_slot| {
+            slots_building_on(head, Slot::new(current_slot))
+                .map(|slot| slot.as_u64())
+                .collect::<Vec<_>>()
+        };
+
+        // The block of the current slot has arrived.
+        assert_eq!(slots(head(10), 10), vec![11]);
+        // It has not arrived yet, or the slot was missed.
+        assert_eq!(slots(head(9), 10), vec![10, 11]);
+        // Far behind, e.g. while syncing.
+        assert_eq!(slots(head(2), 10), vec![10, 11]);
+        assert!(slots(head(12), 10).is_empty());
+    }
+}
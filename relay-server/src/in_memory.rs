@@ -0,0 +1,1619 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use builder_api_types::{
+    registration::{changed_registrations, RegistrationError, RegistrationVerifier},
+    unblind::unblind_block,
+    GetHeaderParams, GetHeaderResponse, SignedBlindedBeaconBlock, SubmitBlindedBlockResponse,
+    VersionedResponse,
+};
+use parking_lot::RwLock;
+use relay_api_types::{
+    ApiKey, BidTraceV1, BuilderDemotion, BuilderEntry, BuilderInfo, ErrorResponse,
+    GetArchivedPayloadQueryParams, GetArchivedPayloadResponse, GetBuilderDemotionsQueryParams,
+    GetBuilderDemotionsResponse, GetBuilderResponse, GetDeliveredPayloadsQueryParams,
+    GetDeliveredPayloadsResponse, GetReceivedBidTracesResponse, GetReceivedBidsQueryParams,
+    GetReceivedBidsResponse, GetValidatorRegistrationQueryParams, GetValidatorRegistrationResponse,
+    GetValidatorsResponse, ListBuildersResponse, Response, RotateApiKeyResponse,
+    SubmitBlockQueryParams, SubmitBlockRequest, SubmitBlockResponse, UpdateBuilderRequest,
+    UpdateBuilderResponse,
+};
+use tracing::warn;
+use types::{
+    eth_spec::EthSpec, ChainSpec, ExecPayload, ExecutionBlockHash, Hash256, PublicKeyBytes,
+    SecretKey, SignedValidatorRegistrationData, Slot,
+};
+
+use crate::{
+    admin::BuilderRegistry,
+    archive::{self, Archive, PayloadArchive},
+    auction::{Auction, Bid},
+    builder::Builder,
+    data::Data,
+    dedup::{DuplicateStore, SubmissionKey},
+    duties::{ProposerDuty, ProposerSchedule},
+    proposer::Proposer,
+    server::constant_time_eq,
+    simulator::{is_invalid_block_code, BlockSimulationRequest, BlockSimulator, SimulationError},
+    slot_clock::{SlotClock, SubmissionFilter},
+    storage::{self, BidFilter, MemoryStorage, Storage, StoredBid},
+    validation::validate_against_registration,
+};
+
+/// Default and maximum number of entries returned by `get_delivered_payloads`.
+pub const MAX_DELIVERED_PAYLOADS_LIMIT: u64 = 200;
+/// Default and maximum number of entries returned by `get_received_bids`.
+pub const MAX_RECEIVED_BIDS_LIMIT: u64 = 500;
+/// Number of slots before the current slot to keep submissions, auctions and duties of when
+/// pruning, see [`InMemoryRelay::prune`].
+pub const STATE_RETENTION_SLOTS: u64 = 64;
+
+/// A reference relay keeping all state in memory.
+///
+/// Cloning is cheap and clones share state.
+pub struct InMemoryRelay<E: EthSpec> {
+    state: Arc<RwLock<State<E>>>,
+    storage: Arc<dyn Storage>,
+    archive: Option<Arc<dyn PayloadArchive<E>>>,
+    simulator: Option<Arc<dyn BlockSimulator<E>>>,
+    submission_filter: Option<SubmissionFilter>,
+    duplicate_store: Option<Arc<dyn DuplicateStore>>,
+    schedule: Arc<ProposerSchedule>,
+    registration_verifier: Option<RegistrationVerifier>,
+    signer: Option<Arc<Signer>>,
+}
+
+/// The relay's key signing bids served to proposers, and what is needed to check the signatures
+/// of their blinded blocks.
+struct Signer {
+    secret_key: SecretKey,
+    pubkey: PublicKeyBytes,
+    spec: ChainSpec,
+    genesis_validators_root: Hash256,
+}
+
+impl<E: EthSpec> Clone for InMemoryRelay<E> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            storage: self.storage.clone(),
+            archive: self.archive.clone(),
+            simulator: self.simulator.clone(),
+            submission_filter: self.submission_filter,
+            duplicate_store: self.duplicate_store.clone(),
+            schedule: self.schedule.clone(),
+            registration_verifier: self.registration_verifier,
+            signer: self.signer.clone(),
+        }
+    }
+}
+
+impl<E: EthSpec> Default for InMemoryRelay<E> {
+    fn default() -> Self {
+        Self {
+            state: Default::default(),
+            storage: Arc::new(MemoryStorage::new()),
+            archive: None,
+            simulator: None,
+            submission_filter: None,
+            duplicate_store: None,
+            schedule: Arc::new(ProposerSchedule::new()),
+            registration_verifier: None,
+            signer: None,
+        }
+    }
+}
+
+struct State<E: EthSpec> {
+    submissions: HashMap<ExecutionBlockHash, Arc<SubmitBlockRequest<E>>>,
+    auctions: HashMap<BidKey, Auction<Arc<SubmitBlockRequest<E>>>>,
+    parent_beacon_block_roots: BTreeMap<Slot, Hash256>,
+    /// Hash and gas limit of the execution block the block at a slot builds on.
+    parent_gas_limits: BTreeMap<Slot, (ExecutionBlockHash, u64)>,
+    builders: HashMap<PublicKeyBytes, BuilderInfo>,
+    api_keys: HashMap<PublicKeyBytes, String>,
+    demotions: Vec<BuilderDemotion>,
+}
+
+impl<E: EthSpec> Default for State<E> {
+    fn default() -> Self {
+        Self {
+            submissions: Default::default(),
+            auctions: Default::default(),
+            parent_beacon_block_roots: Default::default(),
+            parent_gas_limits: Default::default(),
+            builders: Default::default(),
+            api_keys: Default::default(),
+            demotions: Default::default(),
+        }
+    }
+}
+
+/// The auction a bid competes in.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct BidKey {
+    slot: Slot,
+    parent_hash: ExecutionBlockHash,
+    proposer_pubkey: PublicKeyBytes,
+}
+
+impl From<&BidTraceV1> for BidKey {
+    fn from(trace: &BidTraceV1) -> Self {
+        Self {
+            slot: trace.slot,
+            parent_hash: trace.parent_hash,
+            proposer_pubkey: trace.proposer_pubkey,
+        }
+    }
+}
+
+fn error<T>(code: u16, message: impl Into<String>) -> Response<T> {
+    Response::Error(ErrorResponse {
+        code,
+        message: message.into(),
+        stacktraces: None,
+    })
+}
+
+fn simulation_error<T>(e: SimulationError) -> Response<T> {
+    match e {
+        SimulationError::Rpc { code, message } if is_invalid_block_code(code) => {
+            error(400, format!("block simulation failed: {message}"))
+        }
+        e => error(500, format!("unable to simulate block: {e:?}")),
+    }
+}
+
+fn storage_error<T>(e: storage::Error) -> Response<T> {
+    error(500, format!("storage error: {e:?}"))
+}
+
+fn archive_error<T>(e: archive::Error) -> Response<T> {
+    error(500, format!("archive error: {e:?}"))
+}
+
+fn registration_error<T>(
+    registrations: &[SignedValidatorRegistrationData],
+    e: RegistrationError,
+) -> Response<T> {
+    match e.index() {
+        Some(index) => error(
+            400,
+            format!(
+                "invalid registration for {}: {e}",
+                registrations[index].message.pubkey
+            ),
+        ),
+        None => error(400, format!("invalid registrations: {e}")),
+    }
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+impl<E: EthSpec> InMemoryRelay<E> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep received bids, delivered payloads and validator registrations in `storage` instead
+    /// of in memory.
+    pub fn with_storage(mut self, storage: Arc<dyn Storage>) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    /// Keep the full submission of every delivered payload in `archive`.
+    pub fn with_archive(mut self, archive: Arc<dyn PayloadArchive<E>>) -> Self {
+        self.archive = Some(archive);
+        self
+    }
+
+    /// Simulate every submission with `simulator` before accepting it.
+    pub fn with_simulator(mut self, simulator: Arc<dyn BlockSimulator<E>>) -> Self {
+        self.simulator = Some(simulator);
+        self
+    }
+
+    /// Reject submissions for past or far-future slots, or received after the cutoff into their
+    /// slot, before any other processing.
+    pub fn with_submission_filter(mut self, submission_filter: SubmissionFilter) -> Self {
+        self.submission_filter = Some(submission_filter);
+        self
+    }
+
+    /// Acknowledge submissions already accepted by this relay, or any relay sharing
+    /// `duplicate_store`, without processing them again.
+    pub fn with_duplicate_store(mut self, duplicate_store: Arc<dyn DuplicateStore>) -> Self {
+        self.duplicate_store = Some(duplicate_store);
+        self
+    }
+
+    /// Check the timestamps and signatures of registrations received through the Builder API
+    /// with `registration_verifier`. Without one registrations are accepted unchecked.
+    pub fn with_registration_verifier(
+        mut self,
+        registration_verifier: RegistrationVerifier,
+    ) -> Self {
+        self.registration_verifier = Some(registration_verifier);
+        self
+    }
+
+    /// Serve the best bid of each auction to its proposer signed with `secret_key`, and return
+    /// the payload for blinded blocks signed by that proposer, recording it as delivered. Without
+    /// a key the Proposer API only accepts registrations.
+    pub fn with_signing_key(
+        mut self,
+        secret_key: SecretKey,
+        spec: ChainSpec,
+        genesis_validators_root: Hash256,
+    ) -> Self {
+        self.signer = Some(Arc::new(Signer {
+            pubkey: secret_key.public_key().compress(),
+            secret_key,
+            spec,
+            genesis_validators_root,
+        }));
+        self
+    }
+
+    /// Serve `get_validators` from `schedule`, e.g. one kept up to date with
+    /// [`duties::refresh_every_epoch`](crate::duties::refresh_every_epoch).
+    pub fn with_proposer_schedule(mut self, schedule: Arc<ProposerSchedule>) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    /// Record the beacon block root the block at `slot` builds on, which Deneb and later
+    /// simulations need.
+    pub fn set_parent_beacon_block_root(&self, slot: Slot, root: Hash256) {
+        self.state
+            .write()
+            .parent_beacon_block_roots
+            .insert(slot, root);
+    }
+
+    /// Record the execution block the block at `slot` builds on and its gas limit, which bounds
+    /// the gas limit of submissions building on it.
+    pub fn set_parent_gas_limit(
+        &self,
+        slot: Slot,
+        parent_hash: ExecutionBlockHash,
+        gas_limit: u64,
+    ) {
+        self.state
+            .write()
+            .parent_gas_limits
+            .insert(slot, (parent_hash, gas_limit));
+    }
+
+    /// Set the status and collateral of a builder. Builders without an entry have the default
+    /// status and no collateral.
+    pub fn set_builder_info(&self, builder_pubkey: PublicKeyBytes, info: BuilderInfo) {
+        self.state.write().builders.insert(builder_pubkey, info);
+    }
+
+    pub fn builder_info(&self, builder_pubkey: &PublicKeyBytes) -> BuilderInfo {
+        self.state
+            .read()
+            .builders
+            .get(builder_pubkey)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Revoke optimistic status from the builder of an optimistically accepted block that failed
+    /// simulation, and withdraw all its bids for the slot.
+    fn demote(&self, trace: &BidTraceV1, reason: String) {
+        warn!(
+            builder_pubkey = %trace.builder_pubkey,
+            block_hash = %trace.block_hash,
+            reason = %reason,
+            "Demoting builder"
+        );
+
+        let mut state = self.state.write();
+        state
+            .builders
+            .entry(trace.builder_pubkey)
+            .or_default()
+            .status
+            .is_optimistic = false;
+
+        for (_, auction) in state
+            .auctions
+            .iter_mut()
+            .filter(|(key, _)| key.slot == trace.slot)
+        {
+            auction.remove(&trace.builder_pubkey);
+        }
+
+        state.demotions.push(BuilderDemotion {
+            slot: trace.slot,
+            builder_pubkey: trace.builder_pubkey,
+            block_hash: trace.block_hash,
+            value: trace.value,
+            reason,
+            timestamp_ms: now_ms(),
+        });
+    }
+
+    /// Store a validator registration, replacing any previous one for the same pubkey.
+    pub async fn register_validator(
+        &self,
+        registration: SignedValidatorRegistrationData,
+    ) -> Result<(), storage::Error> {
+        self.storage
+            .insert_registration(registration.clone())
+            .await?;
+        self.schedule.insert_registration(registration);
+        Ok(())
+    }
+
+    /// Record that `pubkey` is scheduled to propose at `slot`.
+    pub fn set_proposer_duty(&self, slot: Slot, validator_index: u64, pubkey: PublicKeyBytes) {
+        self.schedule.set_duty(ProposerDuty {
+            slot,
+            validator_index,
+            pubkey,
+        });
+    }
+
+    /// The highest value submission for the given auction.
+    pub fn best_bid(
+        &self,
+        slot: Slot,
+        parent_hash: ExecutionBlockHash,
+        proposer_pubkey: PublicKeyBytes,
+    ) -> Option<Arc<SubmitBlockRequest<E>>> {
+        let key = BidKey {
+            slot,
+            parent_hash,
+            proposer_pubkey,
+        };
+        self.state
+            .read()
+            .auctions
+            .get(&key)?
+            .top()
+            .map(|bid| bid.submission.clone())
+    }
+
+    /// Record the submission with `block_hash` as delivered to the proposer of its slot and
+    /// return it. Returns `None` if no such submission was received.
+    pub async fn deliver_payload(
+        &self,
+        block_hash: ExecutionBlockHash,
+    ) -> Result<Option<Arc<SubmitBlockRequest<E>>>, storage::Error> {
+        let Some(submission) = self.state.read().submissions.get(&block_hash).cloned() else {
+            return Ok(None);
+        };
+        self.storage
+            .insert_delivered_payload(StoredBid {
+                trace: submission.message().clone(),
+                timestamp_ms: now_ms(),
+                ms_into_slot: None,
+            })
+            .await?;
+        // Failing to archive must not keep the payload from the proposer.
+        if let Some(archive) = &self.archive {
+            if let Err(e) = archive.insert(&submission).await {
+                warn!(block_hash = %block_hash, error = ?e, "Failed to archive payload");
+            }
+        }
+        Ok(Some(submission))
+    }
+
+    /// Validate, simulate and store a submission that passed the timing and duplicate checks.
+    async fn accept_submission(
+        &self,
+        query_params: SubmitBlockQueryParams,
+        body: SubmitBlockRequest<E>,
+        received_at_ms: i64,
+        ms_into_slot: Option<i64>,
+    ) -> SubmitBlockResponse {
+        let trace = body.message().clone();
+
+        // The schedule keeps the newest registration, as served by the validators endpoint.
+        let registration = match self.schedule.registration(&trace.proposer_pubkey) {
+            Some(registration) => Some(registration),
+            None => match self.storage.registration(&trace.proposer_pubkey).await {
+                Ok(registration) => registration,
+                Err(e) => return storage_error(e),
+            },
+        };
+        let duty = self.schedule.duty(trace.slot);
+
+        let (builder, validation, registered_gas_limit, parent_beacon_block_root) = {
+            let state = self.state.read();
+            let registration = registration.as_ref();
+            (
+                state
+                    .builders
+                    .get(&trace.builder_pubkey)
+                    .cloned()
+                    .unwrap_or_default(),
+                validate_against_registration(
+                    &trace,
+                    duty.as_ref().map(|duty| &duty.pubkey),
+                    registration,
+                    state
+                        .parent_gas_limits
+                        .get(&trace.slot)
+                        .filter(|(parent_hash, _)| *parent_hash == trace.parent_hash)
+                        .map(|(_, gas_limit)| *gas_limit),
+                ),
+                registration.map_or(trace.gas_limit, |entry| entry.message.gas_limit),
+                state.parent_beacon_block_roots.get(&trace.slot).copied(),
+            )
+        };
+
+        if builder.status.is_blacklisted {
+            return error(403, "builder is blacklisted");
+        }
+        if let Err(e) = validation {
+            return e.to_response();
+        }
+
+        // Optimistic submissions are accepted now and simulated once their bid is in the auction,
+        // so that a demotion always finds the bid to withdraw.
+        let (body, optimistic_simulation) = match &self.simulator {
+            Some(simulator) => {
+                let request = match BlockSimulationRequest::new(
+                    body,
+                    registered_gas_limit,
+                    parent_beacon_block_root,
+                ) {
+                    Ok(request) => request,
+                    Err(e) => return simulation_error(e),
+                };
+                if builder.status.is_optimistic && trace.value <= builder.collateral {
+                    (
+                        request.clone().into_submission(),
+                        Some((simulator.clone(), request)),
+                    )
+                } else {
+                    if let Err(e) = simulator.simulate(&request).await {
+                        return simulation_error(e);
+                    }
+                    (request.into_submission(), None)
+                }
+            }
+            None => (body, None),
+        };
+
+        if let Err(e) = self
+            .storage
+            .insert_received_bid(StoredBid {
+                trace: trace.clone(),
+                timestamp_ms: received_at_ms,
+                ms_into_slot,
+            })
+            .await
+        {
+            return storage_error(e);
+        }
+
+        let submission = Arc::new(body);
+        let key = BidKey::from(&trace);
+
+        {
+            let mut state = self.state.write();
+            state
+                .submissions
+                .insert(trace.block_hash, submission.clone());
+
+            state.auctions.entry(key).or_default().insert(
+                trace.builder_pubkey,
+                Bid {
+                    value: trace.value,
+                    received_at_ms,
+                    submission,
+                },
+                query_params.cancellations.unwrap_or(false),
+            );
+        }
+
+        if let Some((simulator, request)) = optimistic_simulation {
+            let relay = self.clone();
+            let trace = trace.clone();
+            tokio::spawn(async move {
+                match simulator.simulate(&request).await {
+                    Ok(()) => {}
+                    Err(SimulationError::Rpc { code, message }) if is_invalid_block_code(code) => {
+                        relay.demote(&trace, message)
+                    }
+                    Err(e) => warn!(
+                        block_hash = %trace.block_hash,
+                        error = ?e,
+                        "Unable to simulate optimistic submission"
+                    ),
+                }
+            });
+        }
+
+        Response::Success(())
+    }
+
+    /// Received bids matching `query_params`, or the error response to a query that is too broad
+    /// or the storage failing.
+    async fn received_bids<T>(
+        &self,
+        query_params: GetReceivedBidsQueryParams,
+    ) -> Result<Vec<StoredBid>, Response<T>> {
+        if query_params.slot.is_none()
+            && query_params.block_hash.is_none()
+            && query_params.block_number.is_none()
+            && query_params.builder_pubkey.is_none()
+        {
+            return Err(error(
+                400,
+                "need to query for specific slot, block_hash, block_number or builder_pubkey",
+            ));
+        }
+        let limit = match query_params.limit.map(|limit| limit.as_u64()) {
+            Some(limit) if limit > MAX_RECEIVED_BIDS_LIMIT => {
+                return Err(error(
+                    400,
+                    format!("maximum limit is {MAX_RECEIVED_BIDS_LIMIT}"),
+                ))
+            }
+            Some(limit) => limit as usize,
+            None => MAX_RECEIVED_BIDS_LIMIT as usize,
+        };
+
+        self.storage
+            .received_bids(&BidFilter::from(&query_params), limit)
+            .await
+            .map_err(storage_error)
+    }
+
+    /// Drop all submissions, auctions and duties for slots before `slot`. Received bids and
+    /// delivered payloads are kept until pruned from storage.
+    pub fn prune(&self, slot: Slot) {
+        self.schedule.prune(slot);
+        let mut state = self.state.write();
+        state.parent_beacon_block_roots = state.parent_beacon_block_roots.split_off(&slot);
+        state.parent_gas_limits = state.parent_gas_limits.split_off(&slot);
+        state
+            .submissions
+            .retain(|_, submission| submission.message().slot >= slot);
+        state.auctions.retain(|key, _| key.slot >= slot);
+    }
+}
+
+#[async_trait]
+impl<E: EthSpec> Builder<E> for InMemoryRelay<E> {
+    async fn get_validators(&self) -> GetValidatorsResponse {
+        Response::Success(self.schedule.validators())
+    }
+
+    async fn submit_block(
+        &self,
+        query_params: SubmitBlockQueryParams,
+        body: SubmitBlockRequest<E>,
+    ) -> SubmitBlockResponse {
+        let received_at = SlotClock::now_duration();
+        let received_at_ms = received_at.as_millis() as i64;
+
+        let ms_into_slot = match &self.submission_filter {
+            Some(filter) => match filter.check(body.message().slot, received_at) {
+                Ok(ms_into_slot) => Some(ms_into_slot),
+                Err(e) => return error(400, format!("invalid submission timing: {e:?}")),
+            },
+            None => None,
+        };
+
+        // Claim the submission before any work, so that concurrent duplicates are acknowledged
+        // without processing them again.
+        let submission_key = SubmissionKey::from(body.message());
+        if let Some(duplicate_store) = &self.duplicate_store {
+            if !duplicate_store
+                .insert_if_absent(submission_key.clone())
+                .await
+            {
+                return Response::Success(());
+            }
+        }
+
+        let response = self
+            .accept_submission(query_params, body, received_at_ms, ms_into_slot)
+            .await;
+        if let (Response::Error(_), Some(duplicate_store)) = (&response, &self.duplicate_store) {
+            duplicate_store.remove(&submission_key).await;
+        }
+        response
+    }
+}
+
+#[async_trait]
+impl<E: EthSpec> Proposer<E> for InMemoryRelay<E> {
+    async fn register_validators(
+        &self,
+        body: Vec<SignedValidatorRegistrationData>,
+    ) -> Response<()> {
+        let schedule = self.schedule.clone();
+        let previous =
+            move |pubkey: &PublicKeyBytes| schedule.registration(pubkey).map(|kept| kept.message);
+        let mut changed = changed_registrations(&body, &previous)
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if let Some(verifier) = self.registration_verifier {
+            let now = (now_ms() / 1000) as u64;
+            let verified = tokio::task::spawn_blocking(move || {
+                let result = verifier.verify(&changed.iter().collect::<Vec<_>>(), now, previous);
+                (changed, result)
+            })
+            .await;
+            changed = match verified {
+                Ok((changed, Ok(()))) => changed,
+                Ok((changed, Err(e))) => return registration_error(&changed, e),
+                Err(e) => return error(500, format!("unable to verify registrations: {e:?}")),
+            };
+        }
+
+        for registration in changed {
+            if let Err(e) = self.register_validator(registration).await {
+                return storage_error(e);
+            }
+        }
+        Response::Success(())
+    }
+
+    async fn get_header(&self, params: GetHeaderParams) -> Response<Option<GetHeaderResponse<E>>> {
+        let Some(signer) = &self.signer else {
+            return error(501, "headers are not served by this relay");
+        };
+        let Some(submission) = self.best_bid(params.slot, params.parent_hash, params.pubkey) else {
+            return Response::Success(None);
+        };
+        Response::Success(Some(VersionedResponse {
+            version: submission.fork_name(),
+            data: submission.signed_builder_bid(signer.pubkey, &signer.secret_key, &signer.spec),
+        }))
+    }
+
+    async fn submit_blinded_block(
+        &self,
+        body: SignedBlindedBeaconBlock<E>,
+    ) -> Response<SubmitBlindedBlockResponse<E>> {
+        let Some(signer) = &self.signer else {
+            return error(501, "blinded blocks are not accepted by this relay");
+        };
+        let block = body.message();
+        let Ok(header) = block.body().execution_payload() else {
+            return error(400, "blinded block has no execution payload header");
+        };
+        let block_hash = header.block_hash();
+        let Some(submission) = self.state.read().submissions.get(&block_hash).cloned() else {
+            return error(400, format!("no bid with block hash {block_hash}"));
+        };
+
+        let trace = submission.message();
+        if block.slot() != trace.slot {
+            return error(
+                400,
+                format!("bid is for slot {}, not {}", trace.slot, block.slot()),
+            );
+        }
+        let Ok(proposer_pubkey) = trace.proposer_pubkey.decompress() else {
+            return error(
+                400,
+                format!("invalid proposer pubkey {}", trace.proposer_pubkey),
+            );
+        };
+        let fork = signer
+            .spec
+            .fork_at_epoch(block.slot().epoch(E::slots_per_epoch()));
+        if !body.verify_signature(
+            None,
+            &proposer_pubkey,
+            &fork,
+            signer.genesis_validators_root,
+            &signer.spec,
+        ) {
+            return error(400, "invalid blinded block signature");
+        }
+
+        let payload = submission.payload_response();
+        if let Err(e) = unblind_block(body, payload.clone()) {
+            return error(400, format!("blinded block does not match the bid: {e}"));
+        }
+        if let Err(e) = self.deliver_payload(block_hash).await {
+            return storage_error(e);
+        }
+        Response::Success(VersionedResponse {
+            version: payload.fork_name(),
+            data: payload,
+        })
+    }
+
+    async fn status(&self) -> Response<()> {
+        Response::Success(())
+    }
+}
+
+#[async_trait]
+impl<E: EthSpec> BuilderRegistry for InMemoryRelay<E> {
+    async fn list_builders(&self) -> ListBuildersResponse {
+        let mut builders = self
+            .state
+            .read()
+            .builders
+            .iter()
+            .map(|(builder_pubkey, info)| BuilderEntry {
+                builder_pubkey: *builder_pubkey,
+                info: info.clone(),
+            })
+            .collect::<Vec<_>>();
+        builders.sort_by(|a, b| {
+            a.builder_pubkey
+                .as_serialized()
+                .cmp(b.builder_pubkey.as_serialized())
+        });
+        Response::Success(builders)
+    }
+
+    async fn get_builder(&self, builder_pubkey: PublicKeyBytes) -> GetBuilderResponse {
+        match self.state.read().builders.get(&builder_pubkey) {
+            Some(info) => Response::Success(BuilderEntry {
+                builder_pubkey,
+                info: info.clone(),
+            }),
+            None => error(404, "unknown builder"),
+        }
+    }
+
+    async fn update_builder(
+        &self,
+        builder_pubkey: PublicKeyBytes,
+        body: UpdateBuilderRequest,
+    ) -> UpdateBuilderResponse {
+        let mut state = self.state.write();
+        let info = state.builders.entry(builder_pubkey).or_default();
+        body.apply(info);
+        Response::Success(BuilderEntry {
+            builder_pubkey,
+            info: info.clone(),
+        })
+    }
+
+    async fn rotate_api_key(&self, builder_pubkey: PublicKeyBytes) -> RotateApiKeyResponse {
+        let api_key = serde_utils::hex::encode(rand::random::<[u8; 32]>());
+        let mut state = self.state.write();
+        state.builders.entry(builder_pubkey).or_default();
+        state.api_keys.insert(builder_pubkey, api_key.clone());
+        Response::Success(ApiKey { api_key })
+    }
+
+    fn verify_api_key(&self, builder_pubkey: &PublicKeyBytes, api_key: &str) -> bool {
+        self.state
+            .read()
+            .api_keys
+            .get(builder_pubkey)
+            .is_some_and(|key| constant_time_eq(key.as_bytes(), api_key.as_bytes()))
+    }
+}
+
+#[async_trait]
+impl<E: EthSpec> Data for InMemoryRelay<E> {
+    async fn get_delivered_payloads(
+        &self,
+        query_params: GetDeliveredPayloadsQueryParams,
+    ) -> GetDeliveredPayloadsResponse {
+        if query_params.slot.is_some() && query_params.cursor.is_some() {
+            return error(400, "cannot specify both slot and cursor");
+        }
+        let limit = match query_params.limit.map(|limit| limit.as_u64()) {
+            Some(limit) if limit > MAX_DELIVERED_PAYLOADS_LIMIT => {
+                return error(
+                    400,
+                    format!("maximum limit is {MAX_DELIVERED_PAYLOADS_LIMIT}"),
+                )
+            }
+            Some(limit) => limit as usize,
+            None => MAX_DELIVERED_PAYLOADS_LIMIT as usize,
+        };
+
+        match self
+            .storage
+            .delivered_payloads(
+                &BidFilter::from(&query_params),
+                query_params.order_by,
+                limit,
+            )
+            .await
+        {
+            Ok(delivered) => Response::Success(
+                delivered
+                    .iter()
+                    .map(StoredBid::to_bid_trace_v2_with_timestamp)
+                    .collect(),
+            ),
+            Err(e) => storage_error(e),
+        }
+    }
+
+    async fn get_received_bids(
+        &self,
+        query_params: GetReceivedBidsQueryParams,
+    ) -> GetReceivedBidsResponse {
+        match self.received_bids(query_params).await {
+            Ok(bids) => Response::Success(bids.iter().map(StoredBid::to_bid_trace_v2).collect()),
+            Err(response) => response,
+        }
+    }
+
+    async fn get_received_bid_traces(
+        &self,
+        query_params: GetReceivedBidsQueryParams,
+    ) -> Option<GetReceivedBidTracesResponse> {
+        Some(match self.received_bids(query_params).await {
+            Ok(bids) => {
+                Response::Success(bids.iter().map(StoredBid::to_received_bid_trace).collect())
+            }
+            Err(response) => response,
+        })
+    }
+
+    async fn get_validator_registration(
+        &self,
+        query_params: GetValidatorRegistrationQueryParams,
+    ) -> GetValidatorRegistrationResponse {
+        match self.storage.registration(&query_params.pubkey).await {
+            Ok(Some(registration)) => Response::Success(registration),
+            Ok(None) => error(404, "no registration found for validator"),
+            Err(e) => storage_error(e),
+        }
+    }
+
+    async fn get_builder_demotions(
+        &self,
+        query_params: GetBuilderDemotionsQueryParams,
+    ) -> GetBuilderDemotionsResponse {
+        let demotions = self
+            .state
+            .read()
+            .demotions
+            .iter()
+            .rev()
+            .filter(|demotion| {
+                query_params.slot.is_none_or(|slot| demotion.slot == slot)
+                    && query_params
+                        .builder_pubkey
+                        .is_none_or(|pubkey| demotion.builder_pubkey == pubkey)
+            })
+            .cloned()
+            .collect();
+        Response::Success(demotions)
+    }
+}
+
+#[async_trait]
+impl<E: EthSpec> Archive<E> for InMemoryRelay<E> {
+    async fn get_archived_payload(
+        &self,
+        query_params: GetArchivedPayloadQueryParams,
+    ) -> GetArchivedPayloadResponse<E> {
+        let Some(archive) = &self.archive else {
+            return error(404, "payload archive is disabled");
+        };
+
+        let payload = match (query_params.block_hash, query_params.slot) {
+            (Some(block_hash), slot) => match archive.get_by_block_hash(&block_hash).await {
+                Ok(payload) => {
+                    payload.filter(|payload| slot.is_none_or(|slot| payload.message().slot == slot))
+                }
+                Err(e) => return archive_error(e),
+            },
+            (None, Some(slot)) => match archive.get_by_slot(slot).await {
+                Ok(payloads) if payloads.len() > 1 => {
+                    return error(
+                        409,
+                        "multiple payloads archived for slot, query by block_hash",
+                    )
+                }
+                Ok(payloads) => payloads.into_iter().next(),
+                Err(e) => return archive_error(e),
+            },
+            (None, None) => return error(400, "need to query for specific slot or block_hash"),
+        };
+
+        match payload {
+            Some(payload) => Response::Success(VersionedResponse {
+                version: payload.fork_name(),
+                data: payload,
+            }),
+            None => error(404, "no archived payload found"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use builder_api_types::{verify::BidVerifier, PayloadResponse};
+    use relay_api_types::{BuilderStatus, OrderBy};
+    use serde_utils::quoted_u64::Quoted;
+    use types::{
+        Address, BeaconBlock, BeaconBlockCapella, BlindedPayloadCapella, EmptyBlock,
+        ExecutionPayloadHeaderCapella, ForkName, Keypair, MainnetEthSpec, Uint256,
+    };
+
+    use super::*;
+    use crate::{
+        archive::MemoryPayloadArchive,
+        dedup::InMemoryDuplicateStore,
+        simulator::MockSimulator,
+        test_utils::{block_hash, capella_submission, pubkey, registration, trace, GAS_LIMIT},
+    };
+
+    type E = MainnetEthSpec;
+
+    const SLOT: u64 = 10;
+    const PROPOSER: u8 = 2;
+    const BUILDER: u8 = 1;
+
+    /// A relay expecting `PROPOSER`, which has registered, to propose at `SLOT`.
+    async fn relay() -> InMemoryRelay<E> {
+        let relay = InMemoryRelay::new();
+        relay.set_proposer_duty(Slot::new(SLOT), 0, pubkey(PROPOSER));
+        relay
+            .register_validator(registration(PROPOSER))
+            .await
+            .unwrap();
+        relay
+    }
+
+    async fn submit(relay: &InMemoryRelay<E>, block: u8, value: u64) -> SubmitBlockResponse {
+        relay
+            .submit_block(
+                SubmitBlockQueryParams {
+                    cancellations: None,
+                },
+                capella_submission(trace(SLOT, BUILDER, PROPOSER, block, value)),
+            )
+            .await
+    }
+
+    fn best_block_hash(relay: &InMemoryRelay<E>) -> Option<ExecutionBlockHash> {
+        relay
+            .best_bid(Slot::new(SLOT), block_hash(0), pubkey(PROPOSER))
+            .map(|submission| submission.message().block_hash)
+    }
+
+    #[tokio::test]
+    async fn optimistic_submission_demoted() {
+        let relay = relay()
+            .await
+            .with_simulator(Arc::new(MockSimulator::invalid("invalid block")));
+        relay.set_builder_info(
+            pubkey(BUILDER),
+            BuilderInfo {
+                status: BuilderStatus {
+                    is_optimistic: true,
+                    ..Default::default()
+                },
+                collateral: Uint256::from(1_000),
+            },
+        );
+
+        // Above the collateral the block is simulated first and rejected.
+        assert!(matches!(
+            submit(&relay, 3, 2_000).await,
+            Response::Error(ErrorResponse { code: 400, .. })
+        ));
+        assert_eq!(best_block_hash(&relay), None);
+
+        // Within the collateral it is accepted and simulated afterwards.
+        assert_eq!(submit(&relay, 4, 100).await, Response::Success(()));
+        for _ in 0..100 {
+            if !relay.state.read().demotions.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        assert_eq!(best_block_hash(&relay), None);
+        assert!(!relay.builder_info(&pubkey(BUILDER)).status.is_optimistic);
+        let Response::Success(demotions) = relay
+            .get_builder_demotions(GetBuilderDemotionsQueryParams::default())
+            .await
+        else {
+            panic!("unable to get demotions");
+        };
+        assert_eq!(demotions.len(), 1);
+        assert_eq!(demotions[0].block_hash, block_hash(4));
+    }
+
+    #[tokio::test]
+    async fn optimistic_simulation_unavailable() {
+        // Errors of the execution client rather than the block leave the builder optimistic.
+        for code in [-32601, -32603] {
+            let simulator = Arc::new(MockSimulator::rpc_error(code, "unavailable"));
+            let relay = relay().await.with_simulator(simulator.clone());
+            relay.set_builder_info(
+                pubkey(BUILDER),
+                BuilderInfo {
+                    status: BuilderStatus {
+                        is_optimistic: true,
+                        ..Default::default()
+                    },
+                    collateral: Uint256::from(1_000),
+                },
+            );
+
+            // Simulated before accepting, the block is not blamed either.
+            assert!(matches!(
+                submit(&relay, 3, 2_000).await,
+                Response::Error(ErrorResponse { code: 500, .. })
+            ));
+
+            assert_eq!(submit(&relay, 4, 100).await, Response::Success(()));
+            for _ in 0..100 {
+                if simulator.calls() == 2 {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+
+            assert_eq!(best_block_hash(&relay), Some(block_hash(4)));
+            assert!(relay.builder_info(&pubkey(BUILDER)).status.is_optimistic);
+            assert!(relay.state.read().demotions.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn registrations() {
+        let relay = InMemoryRelay::<E>::new();
+        assert_eq!(
+            relay
+                .register_validators(vec![registration(PROPOSER)])
+                .await,
+            Response::Success(())
+        );
+        assert_eq!(
+            relay
+                .get_validator_registration(GetValidatorRegistrationQueryParams {
+                    pubkey: pubkey(PROPOSER),
+                })
+                .await,
+            Response::Success(registration(PROPOSER))
+        );
+
+        // The registration is not signed.
+        let relay = InMemoryRelay::<E>::new()
+            .with_registration_verifier(RegistrationVerifier::new(&E::default_spec()));
+        assert!(matches!(
+            relay
+                .register_validators(vec![registration(PROPOSER)])
+                .await,
+            Response::Error(ErrorResponse { code: 400, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn registration_lookup() {
+        let relay = InMemoryRelay::<E>::new();
+        relay.set_proposer_duty(Slot::new(SLOT), 0, pubkey(PROPOSER));
+
+        // Registrations only in storage, e.g. from before a restart, are used.
+        relay
+            .storage
+            .insert_registration(registration(PROPOSER))
+            .await
+            .unwrap();
+        assert_eq!(submit(&relay, 3, 100).await, Response::Success(()));
+
+        // Otherwise the newest registration is, even if an older one was stored later.
+        let mut newer = registration(PROPOSER);
+        newer.message.fee_recipient = Address::repeat_byte(7);
+        newer.message.timestamp = 1;
+        relay.register_validator(newer).await.unwrap();
+        relay
+            .register_validator(registration(PROPOSER))
+            .await
+            .unwrap();
+        assert!(matches!(
+            submit(&relay, 4, 200).await,
+            Response::Error(ErrorResponse { code: 412, .. })
+        ));
+        let mut message = trace(SLOT, BUILDER, PROPOSER, 5, 300);
+        message.proposer_fee_recipient = Address::repeat_byte(7);
+        let query_params = SubmitBlockQueryParams {
+            cancellations: None,
+        };
+        assert_eq!(
+            relay
+                .submit_block(query_params, capella_submission(message))
+                .await,
+            Response::Success(())
+        );
+    }
+
+    #[tokio::test]
+    async fn parent_gas_limit() {
+        let relay = relay().await;
+
+        // Only the gas limit of the parent the submission builds on is checked.
+        relay.set_parent_gas_limit(Slot::new(SLOT), block_hash(9), GAS_LIMIT / 2);
+        assert_eq!(submit(&relay, 3, 100).await, Response::Success(()));
+
+        relay.set_parent_gas_limit(Slot::new(SLOT), block_hash(0), GAS_LIMIT / 2);
+        assert!(matches!(
+            submit(&relay, 4, 100).await,
+            Response::Error(ErrorResponse { code: 422, .. })
+        ));
+        relay.set_parent_gas_limit(Slot::new(SLOT), block_hash(0), GAS_LIMIT);
+        assert_eq!(submit(&relay, 5, 100).await, Response::Success(()));
+    }
+
+    #[tokio::test]
+    async fn prune() {
+        let relay = relay().await;
+        relay.set_parent_gas_limit(Slot::new(SLOT), block_hash(0), GAS_LIMIT);
+        assert_eq!(submit(&relay, 3, 100).await, Response::Success(()));
+
+        relay.prune(Slot::new(SLOT));
+        assert_eq!(best_block_hash(&relay), Some(block_hash(3)));
+
+        relay.prune(Slot::new(SLOT + 1));
+        assert_eq!(best_block_hash(&relay), None);
+        let state = relay.state.read();
+        assert!(state.submissions.is_empty());
+        assert!(state.parent_gas_limits.is_empty());
+        assert_eq!(relay.schedule.duty(Slot::new(SLOT)), None);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn duplicate_submissions() {
+        let relay = relay()
+            .await
+            .with_duplicate_store(Arc::new(InMemoryDuplicateStore::default()));
+        let received_bids = |relay: InMemoryRelay<E>| async move {
+            let query_params = GetReceivedBidsQueryParams {
+                slot: Some(Slot::new(SLOT)),
+                block_hash: None,
+                block_number: None,
+                builder_pubkey: None,
+                limit: None,
+            };
+            match relay.get_received_bids(query_params).await {
+                Response::Success(bids) => bids.len(),
+                Response::Error(e) => panic!("unable to get received bids: {e:?}"),
+            }
+        };
+
+        let submissions = (0..8)
+            .map(|_| {
+                let relay = relay.clone();
+                tokio::spawn(async move { submit(&relay, 3, 100).await })
+            })
+            .collect::<Vec<_>>();
+        for submission in submissions {
+            assert_eq!(submission.await.unwrap(), Response::Success(()));
+        }
+        assert_eq!(received_bids(relay.clone()).await, 1);
+
+        // A rejected submission is not remembered, so it can be submitted again once valid.
+        let other_slot = || capella_submission::<E>(trace(SLOT + 1, BUILDER, PROPOSER, 4, 100));
+        let query_params = SubmitBlockQueryParams {
+            cancellations: None,
+        };
+        assert!(matches!(
+            relay.submit_block(query_params.clone(), other_slot()).await,
+            Response::Error(ErrorResponse { code: 400, .. })
+        ));
+        relay.set_proposer_duty(Slot::new(SLOT + 1), 0, pubkey(PROPOSER));
+        assert_eq!(
+            relay.submit_block(query_params, other_slot()).await,
+            Response::Success(())
+        );
+        assert!(relay
+            .best_bid(Slot::new(SLOT + 1), block_hash(0), pubkey(PROPOSER))
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn deliver_blinded_block() {
+        let spec = E::default_spec();
+        let genesis_validators_root = Hash256::repeat_byte(5);
+        let relay_key = Keypair::random();
+        let proposer = Keypair::random();
+        let relay = InMemoryRelay::<E>::new()
+            .with_archive(Arc::new(MemoryPayloadArchive::new()))
+            .with_signing_key(relay_key.sk.clone(), spec.clone(), genesis_validators_root);
+
+        let mut message = trace(SLOT, BUILDER, PROPOSER, 3, 100);
+        message.proposer_pubkey = proposer.pk.compress();
+        let mut registration = registration(PROPOSER);
+        registration.message.pubkey = message.proposer_pubkey;
+        relay.set_proposer_duty(Slot::new(SLOT), 0, message.proposer_pubkey);
+        relay.register_validator(registration).await.unwrap();
+        let submission = capella_submission::<E>(message.clone());
+        let query_params = SubmitBlockQueryParams {
+            cancellations: None,
+        };
+        assert_eq!(
+            relay.submit_block(query_params, submission.clone()).await,
+            Response::Success(())
+        );
+
+        // The best bid is served signed by the relay.
+        let Response::Success(Some(bid)) = relay
+            .get_header(GetHeaderParams {
+                slot: Slot::new(SLOT),
+                parent_hash: message.parent_hash,
+                pubkey: message.proposer_pubkey,
+            })
+            .await
+        else {
+            panic!("no header served");
+        };
+        assert_eq!(bid.version, ForkName::Capella);
+        assert_eq!(
+            BidVerifier::new(&spec).verify_signature(&bid.data, &relay_key.pk),
+            Ok(())
+        );
+
+        let SubmitBlockRequest::Capella(capella) = &submission else {
+            unreachable!();
+        };
+        let mut block = BeaconBlockCapella::empty(&spec);
+        block.slot = Slot::new(SLOT);
+        block.body.execution_payload = BlindedPayloadCapella {
+            execution_payload_header: ExecutionPayloadHeaderCapella::from(
+                &capella.execution_payload,
+            ),
+        };
+        let fork = spec.fork_at_epoch(block.slot.epoch(E::slots_per_epoch()));
+        let sign = |keypair: &Keypair| {
+            BeaconBlock::Capella(block.clone()).sign(
+                &keypair.sk,
+                &fork,
+                genesis_validators_root,
+                &spec,
+            )
+        };
+        let delivered = |relay: InMemoryRelay<E>| async move {
+            let query_params = GetDeliveredPayloadsQueryParams {
+                slot: Some(Slot::new(SLOT)),
+                cursor: None,
+                limit: None,
+                block_hash: None,
+                block_number: None,
+                proposer_pubkey: None,
+                builder_pubkey: None,
+                order_by: None,
+            };
+            match relay.get_delivered_payloads(query_params).await {
+                Response::Success(payloads) => payloads.len(),
+                Response::Error(e) => panic!("unable to get delivered payloads: {e:?}"),
+            }
+        };
+
+        // Only the proposer the bid is for gets the payload.
+        assert!(matches!(
+            relay.submit_blinded_block(sign(&Keypair::random())).await,
+            Response::Error(ErrorResponse { code: 400, .. })
+        ));
+        assert_eq!(delivered(relay.clone()).await, 0);
+
+        let Response::Success(payload) = relay.submit_blinded_block(sign(&proposer)).await else {
+            panic!("payload not delivered");
+        };
+        assert_eq!(payload.version, ForkName::Capella);
+        assert_eq!(
+            payload.data,
+            PayloadResponse::Capella(capella.execution_payload.clone())
+        );
+        assert_eq!(delivered(relay.clone()).await, 1);
+
+        let Response::Success(archived) = relay
+            .get_archived_payload(GetArchivedPayloadQueryParams {
+                block_hash: Some(message.block_hash),
+                slot: None,
+            })
+            .await
+        else {
+            panic!("payload not archived");
+        };
+        assert_eq!(archived.version, ForkName::Capella);
+        assert_eq!(archived.data.message(), &message);
+    }
+
+    /// A relay that received and delivered the same bids: blocks 1 to 4 at slots 8, 9, 10 and
+    /// 10, each with its slot as block number.
+    async fn data_relay() -> InMemoryRelay<E> {
+        let relay = InMemoryRelay::new();
+        for (slot, builder, proposer, block, value) in [
+            (8, 1, 2, 1, 300),
+            (9, 3, 4, 2, 100),
+            (10, 1, 4, 3, 200),
+            (10, 3, 2, 4, 400),
+        ] {
+            let mut trace = trace(slot, builder, proposer, block, value);
+            trace.block_number = slot;
+            let bid = StoredBid {
+                trace,
+                timestamp_ms: 0,
+                ms_into_slot: None,
+            };
+            relay
+                .storage
+                .insert_received_bid(bid.clone())
+                .await
+                .unwrap();
+            relay.storage.insert_delivered_payload(bid).await.unwrap();
+        }
+        relay
+    }
+
+    #[tokio::test]
+    async fn delivered_payload_queries() {
+        let relay = data_relay().await;
+        let all = GetDeliveredPayloadsQueryParams {
+            slot: None,
+            cursor: None,
+            limit: None,
+            block_hash: None,
+            block_number: None,
+            proposer_pubkey: None,
+            builder_pubkey: None,
+            order_by: None,
+        };
+
+        let cases = [
+            ("all", all.clone(), vec![4, 3, 2, 1]),
+            (
+                "slot",
+                GetDeliveredPayloadsQueryParams {
+                    slot: Some(Slot::new(10)),
+                    ..all.clone()
+                },
+                vec![4, 3],
+            ),
+            (
+                "cursor",
+                GetDeliveredPayloadsQueryParams {
+                    cursor: Some(Slot::new(9)),
+                    ..all.clone()
+                },
+                vec![2, 1],
+            ),
+            (
+                "limit",
+                GetDeliveredPayloadsQueryParams {
+                    limit: Some(Slot::new(2)),
+                    ..all.clone()
+                },
+                vec![4, 3],
+            ),
+            (
+                "block_hash",
+                GetDeliveredPayloadsQueryParams {
+                    block_hash: Some(block_hash(2)),
+                    ..all.clone()
+                },
+                vec![2],
+            ),
+            (
+                "block_number",
+                GetDeliveredPayloadsQueryParams {
+                    block_number: Some(Quoted { value: 8 }),
+                    ..all.clone()
+                },
+                vec![1],
+            ),
+            (
+                "proposer_pubkey",
+                GetDeliveredPayloadsQueryParams {
+                    proposer_pubkey: Some(pubkey(4)),
+                    ..all.clone()
+                },
+                vec![3, 2],
+            ),
+            (
+                "builder_pubkey",
+                GetDeliveredPayloadsQueryParams {
+                    builder_pubkey: Some(pubkey(1)),
+                    ..all.clone()
+                },
+                vec![3, 1],
+            ),
+            (
+                "builder_pubkey and cursor",
+                GetDeliveredPayloadsQueryParams {
+                    builder_pubkey: Some(pubkey(3)),
+                    cursor: Some(Slot::new(9)),
+                    ..all.clone()
+                },
+                vec![2],
+            ),
+            (
+                "order_by value",
+                GetDeliveredPayloadsQueryParams {
+                    order_by: Some(OrderBy::Value),
+                    ..all.clone()
+                },
+                vec![2, 3, 1, 4],
+            ),
+            (
+                "order_by -value",
+                GetDeliveredPayloadsQueryParams {
+                    order_by: Some(OrderBy::NegativeValue),
+                    ..all.clone()
+                },
+                vec![4, 1, 3, 2],
+            ),
+            (
+                "order_by -value and limit",
+                GetDeliveredPayloadsQueryParams {
+                    order_by: Some(OrderBy::NegativeValue),
+                    limit: Some(Slot::new(2)),
+                    ..all.clone()
+                },
+                vec![4, 1],
+            ),
+            (
+                "order_by value and cursor",
+                GetDeliveredPayloadsQueryParams {
+                    order_by: Some(OrderBy::Value),
+                    cursor: Some(Slot::new(9)),
+                    ..all.clone()
+                },
+                vec![2, 1],
+            ),
+        ];
+        for (name, query_params, blocks) in cases {
+            let Response::Success(payloads) = relay.get_delivered_payloads(query_params).await
+            else {
+                panic!("{name}: unable to get delivered payloads");
+            };
+            let block_hashes = payloads
+                .iter()
+                .map(|payload| payload.bid_trace.bid_trace.block_hash)
+                .collect::<Vec<_>>();
+            let expected = blocks.into_iter().map(block_hash).collect::<Vec<_>>();
+            assert_eq!(block_hashes, expected, "{name}");
+        }
+
+        let invalid = [
+            GetDeliveredPayloadsQueryParams {
+                slot: Some(Slot::new(10)),
+                cursor: Some(Slot::new(10)),
+                ..all.clone()
+            },
+            GetDeliveredPayloadsQueryParams {
+                limit: Some(Slot::new(MAX_DELIVERED_PAYLOADS_LIMIT + 1)),
+                ..all
+            },
+        ];
+        for query_params in invalid {
+            assert!(matches!(
+                relay.get_delivered_payloads(query_params).await,
+                Response::Error(ErrorResponse { code: 400, .. })
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn received_bid_queries() {
+        let relay = data_relay().await;
+        let none = GetReceivedBidsQueryParams {
+            slot: None,
+            block_hash: None,
+            block_number: None,
+            builder_pubkey: None,
+            limit: None,
+        };
+
+        let cases = [
+            (
+                "slot",
+                GetReceivedBidsQueryParams {
+                    slot: Some(Slot::new(10)),
+                    ..none.clone()
+                },
+                vec![4, 3],
+            ),
+            (
+                "slot and limit",
+                GetReceivedBidsQueryParams {
+                    slot: Some(Slot::new(10)),
+                    limit: Some(Slot::new(1)),
+                    ..none.clone()
+                },
+                vec![4],
+            ),
+            (
+                "block_hash",
+                GetReceivedBidsQueryParams {
+                    block_hash: Some(block_hash(3)),
+                    ..none.clone()
+                },
+                vec![3],
+            ),
+            (
+                "block_number",
+                GetReceivedBidsQueryParams {
+                    block_number: Some(Quoted { value: 9 }),
+                    ..none.clone()
+                },
+                vec![2],
+            ),
+            (
+                "builder_pubkey",
+                GetReceivedBidsQueryParams {
+                    builder_pubkey: Some(pubkey(3)),
+                    ..none.clone()
+                },
+                vec![4, 2],
+            ),
+            (
+                "builder_pubkey and slot",
+                GetReceivedBidsQueryParams {
+                    builder_pubkey: Some(pubkey(3)),
+                    slot: Some(Slot::new(9)),
+                    ..none.clone()
+                },
+                vec![2],
+            ),
+        ];
+        for (name, query_params, blocks) in cases {
+            let Response::Success(bids) = relay.get_received_bids(query_params.clone()).await
+            else {
+                panic!("{name}: unable to get received bids");
+            };
+            let block_hashes = bids
+                .iter()
+                .map(|bid| bid.bid_trace.block_hash)
+                .collect::<Vec<_>>();
+            let expected = blocks.into_iter().map(block_hash).collect::<Vec<_>>();
+            assert_eq!(block_hashes, expected, "{name}");
+
+            // The traces are of the same bids.
+            let Some(Response::Success(traces)) = relay.get_received_bid_traces(query_params).await
+            else {
+                panic!("{name}: unable to get received bid traces");
+            };
+            let traced = traces
+                .into_iter()
+                .map(|trace| trace.bid_trace.bid_trace)
+                .collect::<Vec<_>>();
+            assert_eq!(traced, bids, "{name}");
+        }
+
+        // A filter is required and the limit is capped.
+        let invalid = [
+            none.clone(),
+            GetReceivedBidsQueryParams {
+                slot: Some(Slot::new(10)),
+                limit: Some(Slot::new(MAX_RECEIVED_BIDS_LIMIT + 1)),
+                ..none
+            },
+        ];
+        for query_params in invalid {
+            assert!(matches!(
+                relay.get_received_bid_traces(query_params.clone()).await,
+                Some(Response::Error(ErrorResponse { code: 400, .. }))
+            ));
+            assert!(matches!(
+                relay.get_received_bids(query_params).await,
+                Response::Error(ErrorResponse { code: 400, .. })
+            ));
+        }
+    }
+}
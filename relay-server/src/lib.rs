@@ -1,5 +1,24 @@
 pub use relay_api_types::*;
 
+pub mod admin;
+pub mod archive;
+pub mod auction;
 pub mod builder;
+pub mod cache;
+pub mod config;
+pub mod cors;
 pub mod data;
+pub mod dedup;
+pub mod duties;
+pub mod head;
+pub mod in_memory;
+pub mod metrics;
+pub mod proposer;
 pub mod server;
+pub mod simulator;
+pub mod slot_clock;
+pub mod storage;
+#[cfg(test)]
+mod test_utils;
+pub mod tls;
+pub mod validation;
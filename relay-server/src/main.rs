@@ -0,0 +1,393 @@
+use std::{path::PathBuf, process::ExitCode, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use axum::{extract::DefaultBodyLimit, Router};
+use axum_server::Handle;
+use beacon_client::{BeaconNodeHttpClient, SensitiveUrl, Timeouts};
+use builder_api_types::registration::RegistrationVerifier;
+use relay_server::{
+    archive::{FilePayloadArchive, PayloadArchive},
+    builder::Builder,
+    cache::{self, CachePolicy},
+    config::{Backend, Config, Network},
+    cors::cors_layer,
+    data::Data,
+    dedup::InMemoryDuplicateStore,
+    duties::{self, ProposerSchedule},
+    head::{self, DEFAULT_HEAD_POLL_INTERVAL},
+    in_memory::{InMemoryRelay, STATE_RETENTION_SLOTS},
+    metrics::{self, Metrics},
+    server::{self, RouterBuilder},
+    simulator::JsonRpcSimulator,
+    slot_clock::{SlotClock, SubmissionFilter},
+    storage::{FileStorage, MemoryStorage, Storage},
+    tls, ErrorResponse, GetBuilderDemotionsQueryParams, GetBuilderDemotionsResponse,
+    GetDeliveredPayloadsQueryParams, GetDeliveredPayloadsResponse, GetReceivedBidsQueryParams,
+    GetReceivedBidsResponse, GetValidatorRegistrationQueryParams, GetValidatorRegistrationResponse,
+    GetValidatorsResponse, Response, SubmitBlockQueryParams, SubmitBlockRequest,
+    SubmitBlockResponse,
+};
+use tokio::signal;
+use tower::limit::GlobalConcurrencyLimitLayer;
+use tower_http::timeout::TimeoutLayer;
+use tracing::{error, info, warn};
+use types::{EthSpec, GnosisEthSpec, MainnetEthSpec, MinimalEthSpec, SecretKey};
+
+fn main() -> ExitCode {
+    tracing_subscriber::fmt::init();
+
+    let Some(config_path) = std::env::args_os().nth(1).map(PathBuf::from) else {
+        eprintln!("Usage: relay-server <config.toml|config.yaml>");
+        return ExitCode::FAILURE;
+    };
+
+    let config = match Config::load(&config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            error!(error = ?e, path = ?config_path, "Failed to load config");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let runtime = match tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            error!(error = ?e, "Failed to start runtime");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match runtime.block_on(run(config)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!(error = ?e, "Relay server failed");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(config: Config) -> std::io::Result<()> {
+    let router = match config.network {
+        Network::Mainnet => router::<MainnetEthSpec>(&config),
+        Network::Gnosis => router::<GnosisEthSpec>(&config),
+        Network::Minimal => router::<MinimalEthSpec>(&config),
+    }?;
+
+    let router = if config.metrics {
+        let metrics = Metrics::new(Default::default()).map_err(std::io::Error::other)?;
+        metrics::with_metrics(router, metrics)
+    } else {
+        router
+    };
+
+    let router = router
+        .layer(TimeoutLayer::new(config.limits.request_timeout()))
+        .layer(GlobalConcurrencyLimitLayer::new(
+            config.limits.max_concurrent_requests,
+        ))
+        .layer(DefaultBodyLimit::max(config.limits.max_body_bytes));
+
+    let handle = Handle::new();
+    tokio::spawn(shutdown_on_signal(
+        handle.clone(),
+        config.limits.shutdown_timeout(),
+    ));
+
+    info!(address = %config.listen_address, tls = config.tls.is_some(), "Starting relay server");
+
+    match config.tls {
+        Some(tls_config) => tls::serve(router, config.listen_address, tls_config, handle).await,
+        None => {
+            axum_server::bind(config.listen_address)
+                .handle(handle)
+                .serve(router.into_make_service())
+                .await
+        }
+    }
+}
+
+fn router<E: EthSpec>(config: &Config) -> std::io::Result<Router> {
+    let router = match config.backend {
+        Backend::Unavailable => server::new::<_, UnavailableRelay, E>(Arc::new(UnavailableRelay)),
+        Backend::InMemory => {
+            let storage: Arc<dyn Storage> = match &config.storage.path {
+                Some(path) => Arc::new(FileStorage::open(path).map_err(|e| {
+                    std::io::Error::other(format!("unable to open storage: {e:?}"))
+                })?),
+                None => Arc::new(MemoryStorage::new()),
+            };
+            let archive = match &config.storage.archive_path {
+                Some(path) => Some(Arc::new(FilePayloadArchive::open(path).map_err(|e| {
+                    std::io::Error::other(format!("unable to open payload archive: {e:?}"))
+                })?) as Arc<dyn PayloadArchive<E>>),
+                None => None,
+            };
+            let mut relay = InMemoryRelay::<E>::new()
+                .with_storage(storage.clone())
+                .with_duplicate_store(Arc::new(InMemoryDuplicateStore::new(
+                    config.limits.duplicate_window(),
+                )))
+                .with_registration_verifier(RegistrationVerifier::new(
+                    &config.network.chain_spec(),
+                ));
+            if let Some(archive) = &archive {
+                relay = relay.with_archive(archive.clone());
+            }
+            if let Some(signing_key) = &config.signing_key {
+                let secret_key = serde_utils::hex::decode(signing_key)
+                    .ok()
+                    .and_then(|bytes| SecretKey::deserialize(&bytes).ok())
+                    .ok_or_else(|| std::io::Error::other("invalid signing key"))?;
+                let genesis_validators_root =
+                    config.genesis_validators_root().ok_or_else(|| {
+                        std::io::Error::other("signing bids needs a genesis validators root")
+                    })?;
+                relay = relay.with_signing_key(
+                    secret_key,
+                    config.network.chain_spec(),
+                    genesis_validators_root,
+                );
+            }
+            let clock = config.genesis_time().map(|genesis_time| {
+                SlotClock::from_spec(&config.network.chain_spec(), genesis_time)
+            });
+            let mut head_tracking = None;
+            if let Some(clock) = clock {
+                relay = relay.with_submission_filter(SubmissionFilter {
+                    clock,
+                    max_future_slots: config.limits.max_future_slots,
+                    cutoff: config.limits.submission_cutoff(),
+                });
+                if let Some(beacon_node) = &config.beacon_node {
+                    let url = SensitiveUrl::parse(&beacon_node.url).map_err(|e| {
+                        std::io::Error::other(format!("invalid beacon node url: {e:?}"))
+                    })?;
+                    let client = Arc::new(BeaconNodeHttpClient::new(
+                        url,
+                        Timeouts::set_all(beacon_node.timeout()),
+                    ));
+                    let schedule = Arc::new(ProposerSchedule::new());
+                    relay = relay.with_proposer_schedule(schedule.clone());
+                    tokio::spawn(duties::refresh_every_epoch::<E>(
+                        schedule,
+                        client.clone(),
+                        storage.clone(),
+                        clock,
+                    ));
+                    head_tracking = Some((client, clock));
+                }
+            } else if config.beacon_node.is_some() {
+                warn!("No genesis time known, not fetching proposer duties");
+            }
+            if let Some(simulator) = &config.simulator {
+                // Deneb and later simulations need the parent beacon block root.
+                if head_tracking.is_none() {
+                    return Err(std::io::Error::other(
+                        "simulation needs a beacon node and a genesis time",
+                    ));
+                }
+                relay = relay.with_simulator(Arc::new(JsonRpcSimulator::with_timeout(
+                    simulator.url.clone(),
+                    simulator.timeout(),
+                )));
+            }
+            if let Some((client, clock)) = head_tracking {
+                tokio::spawn(head::track_head(
+                    relay.clone(),
+                    client,
+                    clock,
+                    DEFAULT_HEAD_POLL_INTERVAL,
+                ));
+            }
+            if let Some(clock) = clock {
+                tokio::spawn(prune(
+                    relay.clone(),
+                    storage.clone(),
+                    archive.clone(),
+                    clock,
+                    config.storage.retention_slots,
+                    Duration::from_secs(
+                        config.network.chain_spec().seconds_per_slot * E::slots_per_epoch(),
+                    ),
+                ));
+            }
+            let relay = Arc::new(relay);
+            let mut data_routes = server::data_router::<_, InMemoryRelay<E>>(relay.clone());
+            if archive.is_some() {
+                data_routes = data_routes.merge(server::archive_router::<_, InMemoryRelay<E>, E>(
+                    relay.clone(),
+                ));
+            }
+            let routes = RouterBuilder::new()
+                .proposer_api::<_, InMemoryRelay<E>, E>(relay.clone())
+                .merge(public_data_routes(data_routes, config)?);
+            match &config.admin_token {
+                Some(admin_token) => routes
+                    .authenticated_builder_api::<_, InMemoryRelay<E>, E>(relay.clone())
+                    .admin_api::<_, InMemoryRelay<E>>(relay, admin_token.clone())
+                    .build(),
+                None => routes.builder_api::<_, InMemoryRelay<E>, E>(relay).build(),
+            }
+        }
+    };
+    Ok(router)
+}
+
+/// Apply the configured caching headers and CORS policy to Data API routes.
+fn public_data_routes(router: Router, config: &Config) -> std::io::Result<Router> {
+    let router = match (&config.cache, config.genesis_time()) {
+        (Some(cache), Some(genesis_time)) => cache::with_cache_headers(
+            router,
+            CachePolicy {
+                clock: SlotClock::from_spec(&config.network.chain_spec(), genesis_time),
+                finalized_after_slots: cache.finalized_after_slots,
+                max_age: cache.max_age(),
+            },
+        ),
+        (Some(_), None) => {
+            warn!("No genesis time known, not adding caching headers");
+            router
+        }
+        (None, _) => router,
+    };
+
+    match &config.cors {
+        Some(cors) => {
+            let layer = cors_layer(cors).map_err(|e| {
+                std::io::Error::other(format!("invalid CORS allowed origin: {e:?}"))
+            })?;
+            Ok(router.layer(layer))
+        }
+        None => Ok(router),
+    }
+}
+
+/// Every `interval`, drop submissions, auctions and duties older than
+/// [`STATE_RETENTION_SLOTS`] from `relay`, and stored bids and archived payloads older than
+/// `retention_slots` if set.
+async fn prune<E: EthSpec>(
+    relay: InMemoryRelay<E>,
+    storage: Arc<dyn Storage>,
+    archive: Option<Arc<dyn PayloadArchive<E>>>,
+    clock: SlotClock,
+    retention_slots: Option<u64>,
+    interval: Duration,
+) {
+    let mut interval = tokio::time::interval(interval);
+    loop {
+        interval.tick().await;
+        let Some(current_slot) = clock.now() else {
+            continue;
+        };
+        relay.prune(current_slot.saturating_sub(STATE_RETENTION_SLOTS));
+
+        let Some(retention_slots) = retention_slots else {
+            continue;
+        };
+        let oldest_slot = current_slot.saturating_sub(retention_slots);
+        if let Err(e) = storage.prune(oldest_slot).await {
+            warn!(error = ?e, "Failed to prune storage");
+        }
+        if let Some(archive) = &archive {
+            if let Err(e) = archive.prune(oldest_slot).await {
+                warn!(error = ?e, "Failed to prune payload archive");
+            }
+        }
+    }
+}
+
+/// Stop accepting connections on SIGTERM or SIGINT and give in-flight requests `timeout` to
+/// complete.
+async fn shutdown_on_signal(handle: Handle, timeout: Duration) {
+    let interrupt = async {
+        if let Err(e) = signal::ctrl_c().await {
+            error!(error = ?e, "Failed to listen for SIGINT");
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                error!(error = ?e, "Failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => {},
+        _ = terminate => {},
+    }
+
+    info!(timeout = ?timeout, "Shutting down, draining in-flight requests");
+    handle.graceful_shutdown(Some(timeout));
+}
+
+/// Backend used when no relay implementation is configured.
+struct UnavailableRelay;
+
+impl UnavailableRelay {
+    fn error<T>() -> Response<T> {
+        Response::Error(ErrorResponse {
+            code: 503,
+            message: "no relay backend configured".to_string(),
+            stacktraces: None,
+        })
+    }
+}
+
+#[async_trait]
+impl<E: EthSpec> Builder<E> for UnavailableRelay {
+    async fn get_validators(&self) -> GetValidatorsResponse {
+        Self::error()
+    }
+
+    async fn submit_block(
+        &self,
+        _query_params: SubmitBlockQueryParams,
+        _body: SubmitBlockRequest<E>,
+    ) -> SubmitBlockResponse {
+        Self::error()
+    }
+}
+
+#[async_trait]
+impl Data for UnavailableRelay {
+    async fn get_delivered_payloads(
+        &self,
+        _query_params: GetDeliveredPayloadsQueryParams,
+    ) -> GetDeliveredPayloadsResponse {
+        Self::error()
+    }
+
+    async fn get_received_bids(
+        &self,
+        _query_params: GetReceivedBidsQueryParams,
+    ) -> GetReceivedBidsResponse {
+        Self::error()
+    }
+
+    async fn get_validator_registration(
+        &self,
+        _query_params: GetValidatorRegistrationQueryParams,
+    ) -> GetValidatorRegistrationResponse {
+        Self::error()
+    }
+
+    async fn get_builder_demotions(
+        &self,
+        _query_params: GetBuilderDemotionsQueryParams,
+    ) -> GetBuilderDemotionsResponse {
+        Self::error()
+    }
+}
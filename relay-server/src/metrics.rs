@@ -0,0 +1,277 @@
+use std::time::Instant;
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::{MatchedPath, Request, State},
+    middleware::{self, Next},
+    response::Response,
+    routing::get,
+    Router,
+};
+use http::{
+    header::{CONTENT_LENGTH, CONTENT_TYPE},
+    HeaderMap, StatusCode,
+};
+use prometheus::{
+    exponential_buckets, Encoder, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry,
+    TextEncoder,
+};
+use relay_api_types::SubmitBlockRequest;
+use tracing::error;
+use types::eth_spec::EthSpec;
+
+/// Prometheus metrics for the relay API.
+///
+/// All metrics are registered on the `Registry` passed to [`Metrics::new`], so the embedding
+/// application can register its own metrics on the same registry and have them exported by the
+/// `/metrics` route as well.
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    requests_total: IntCounterVec,
+    request_duration_seconds: HistogramVec,
+    request_body_bytes: HistogramVec,
+    response_body_bytes: HistogramVec,
+    decode_failures_total: IntCounterVec,
+    block_submissions_total: IntCounterVec,
+}
+
+impl Metrics {
+    pub fn new(registry: Registry) -> Result<Self, prometheus::Error> {
+        let requests_total = IntCounterVec::new(
+            Opts::new("relay_http_requests_total", "Total number of HTTP requests"),
+            &["endpoint", "method", "status"],
+        )?;
+        let request_duration_seconds = HistogramVec::new(
+            HistogramOpts::new(
+                "relay_http_request_duration_seconds",
+                "Time taken to handle an HTTP request",
+            ),
+            &["endpoint", "method"],
+        )?;
+        let request_body_bytes = HistogramVec::new(
+            HistogramOpts::new(
+                "relay_http_request_body_bytes",
+                "Size of HTTP request bodies",
+            )
+            .buckets(exponential_buckets(256.0, 4.0, 10)?),
+            &["endpoint"],
+        )?;
+        let response_body_bytes = HistogramVec::new(
+            HistogramOpts::new(
+                "relay_http_response_body_bytes",
+                "Size of HTTP response bodies",
+            )
+            .buckets(exponential_buckets(256.0, 4.0, 10)?),
+            &["endpoint"],
+        )?;
+        let decode_failures_total = IntCounterVec::new(
+            Opts::new(
+                "relay_decode_failures_total",
+                "Total number of request bodies that failed to decode",
+            ),
+            &["content_type"],
+        )?;
+        let block_submissions_total = IntCounterVec::new(
+            Opts::new(
+                "relay_block_submissions_total",
+                "Total number of decoded block submissions",
+            ),
+            &["fork"],
+        )?;
+
+        registry.register(Box::new(requests_total.clone()))?;
+        registry.register(Box::new(request_duration_seconds.clone()))?;
+        registry.register(Box::new(request_body_bytes.clone()))?;
+        registry.register(Box::new(response_body_bytes.clone()))?;
+        registry.register(Box::new(decode_failures_total.clone()))?;
+        registry.register(Box::new(block_submissions_total.clone()))?;
+
+        Ok(Self {
+            registry,
+            requests_total,
+            request_duration_seconds,
+            request_body_bytes,
+            response_body_bytes,
+            decode_failures_total,
+            block_submissions_total,
+        })
+    }
+
+    /// The registry all relay metrics are registered on.
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    pub(crate) fn observe_decode_failure(&self, content_type: &str) {
+        self.decode_failures_total
+            .with_label_values(&[content_type])
+            .inc();
+    }
+
+    pub(crate) fn observe_submission<E: EthSpec>(&self, body: &SubmitBlockRequest<E>) {
+        let fork = match body {
+            SubmitBlockRequest::Bellatrix(_) => "bellatrix",
+            SubmitBlockRequest::Capella(_) => "capella",
+            SubmitBlockRequest::Deneb(_) => "deneb",
+            SubmitBlockRequest::Electra(_) => "electra",
+        };
+        self.block_submissions_total
+            .with_label_values(&[fork])
+            .inc();
+    }
+}
+
+/// Instrument every route of `router` and add a `/metrics` route exporting `metrics.registry()`.
+///
+/// Requests not matching a route, and routes added to the returned router afterwards, are not
+/// instrumented.
+pub fn with_metrics(router: Router, metrics: Metrics) -> Router {
+    router
+        .route_layer(middleware::from_fn_with_state(
+            metrics.clone(),
+            track_requests,
+        ))
+        .route("/metrics", get(export).with_state(metrics))
+}
+
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+}
+
+async fn track_requests(
+    State(metrics): State<Metrics>,
+    matched_path: MatchedPath,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let endpoint = matched_path.as_str();
+    let method = req.method().to_string();
+
+    if let Some(len) = content_length(req.headers()) {
+        metrics
+            .request_body_bytes
+            .with_label_values(&[endpoint])
+            .observe(len as f64);
+    }
+
+    // Make the metrics available to extractors and handlers further down the stack.
+    req.extensions_mut().insert(metrics.clone());
+
+    let start = Instant::now();
+    let response = next.run(req).await;
+
+    metrics
+        .request_duration_seconds
+        .with_label_values(&[endpoint, &method])
+        .observe(start.elapsed().as_secs_f64());
+    metrics
+        .requests_total
+        .with_label_values(&[endpoint, &method, response.status().as_str()])
+        .inc();
+    if let Some(len) = response.body().size_hint().exact() {
+        metrics
+            .response_body_bytes
+            .with_label_values(&[endpoint])
+            .observe(len as f64);
+    }
+
+    response
+}
+
+/// Metrics - GET /metrics
+async fn export(State(metrics): State<Metrics>) -> Result<Response<Body>, StatusCode> {
+    let encoder = TextEncoder::new();
+    let mut buffer = vec![];
+    encoder
+        .encode(&metrics.registry.gather(), &mut buffer)
+        .map_err(|e| {
+            error!(error = ?e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Response::builder()
+        .status(200)
+        .header(CONTENT_TYPE, encoder.format_type())
+        .body(Body::from(buffer))
+        .map_err(|e| {
+            error!(error = ?e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tower::ServiceExt;
+    use types::{MainnetEthSpec, Slot};
+
+    use super::*;
+    use crate::{
+        in_memory::InMemoryRelay,
+        server::builder_router,
+        test_utils::{capella_submission, pubkey, registration, trace},
+    };
+
+    type E = MainnetEthSpec;
+
+    #[tokio::test]
+    async fn exported_metrics() {
+        let relay = InMemoryRelay::<E>::new();
+        relay.set_proposer_duty(Slot::new(10), 0, pubkey(2));
+        relay.register_validator(registration(2)).await.unwrap();
+        let metrics = Metrics::new(Registry::new()).unwrap();
+        let router = with_metrics(
+            builder_router::<_, InMemoryRelay<E>, E>(Arc::new(relay)),
+            metrics,
+        );
+
+        let submit = |body: Vec<u8>| {
+            let request = http::Request::post("/relay/v1/builder/blocks")
+                .header(CONTENT_TYPE, "application/json")
+                .header(CONTENT_LENGTH, body.len())
+                .body(Body::from(body))
+                .unwrap();
+            let router = router.clone();
+            async move { router.oneshot(request).await.unwrap().status() }
+        };
+        let submission = capella_submission::<E>(trace(10, 1, 2, 3, 100));
+        assert_eq!(
+            submit(serde_json::to_vec(&submission).unwrap()).await,
+            StatusCode::OK
+        );
+        assert_eq!(submit(b"{".to_vec()).await, StatusCode::BAD_REQUEST);
+
+        let request = http::Request::get("/unknown").body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let request = http::Request::get("/metrics").body(Body::empty()).unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+
+        let endpoint = r#"endpoint="/relay/v1/builder/blocks""#;
+        for line in [
+            format!(r#"relay_http_requests_total{{{endpoint},method="POST",status="200"}} 1"#),
+            format!(r#"relay_http_requests_total{{{endpoint},method="POST",status="400"}} 1"#),
+            format!(r#"relay_http_request_duration_seconds_count{{{endpoint},method="POST"}} 2"#),
+            format!(r#"relay_http_request_body_bytes_count{{{endpoint}}} 2"#),
+            r#"relay_block_submissions_total{fork="capella"} 1"#.to_owned(),
+            r#"relay_decode_failures_total{content_type="json"} 1"#.to_owned(),
+        ] {
+            assert!(
+                text.lines().any(|exported| exported == line),
+                "missing {line} in\n{text}"
+            );
+        }
+        assert!(!text.contains("/unknown"));
+    }
+}
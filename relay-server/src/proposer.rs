@@ -0,0 +1,34 @@
+use async_trait::async_trait;
+use builder_api_types::{
+    GetHeaderParams, GetHeaderResponse, SignedBlindedBeaconBlock, SubmitBlindedBlockResponse,
+};
+use relay_api_types::Response;
+use types::{eth_spec::EthSpec, SignedValidatorRegistrationData};
+
+/// Proposer
+#[async_trait]
+pub trait Proposer<E: EthSpec> {
+    /// Register or update validators' fee recipient, gas limit and timestamp..
+    ///
+    /// RegisterValidators - POST /eth/v1/builder/validators
+    async fn register_validators(&self, body: Vec<SignedValidatorRegistrationData>)
+        -> Response<()>;
+
+    /// Get the best execution payload header for a slot, `None` if there is no bid..
+    ///
+    /// GetHeader - GET /eth/v1/builder/header/{slot}/{parent_hash}/{pubkey}
+    async fn get_header(&self, params: GetHeaderParams) -> Response<Option<GetHeaderResponse<E>>>;
+
+    /// Submit a signed blinded block and get the execution payload back..
+    ///
+    /// SubmitBlindedBlock - POST /eth/v1/builder/blinded_blocks
+    async fn submit_blinded_block(
+        &self,
+        body: SignedBlindedBeaconBlock<E>,
+    ) -> Response<SubmitBlindedBlockResponse<E>>;
+
+    /// Check whether the relay is ready to serve proposers..
+    ///
+    /// Status - GET /eth/v1/builder/status
+    async fn status(&self) -> Response<()>;
+}
@@ -1,38 +1,100 @@
+use std::{str::FromStr, sync::Arc};
+
 use axum::{
     async_trait,
     body::Body,
-    extract::{FromRequest, Query, Request, State},
+    extract::{FromRequest, Path, Query, Request, State},
+    middleware::{self, Next},
     response::{IntoResponse, Response},
-    routing::{get, post},
-    Json, RequestExt, Router,
+    routing::{get, post, MethodRouter},
+    Extension, Json, RequestExt, Router,
+};
+use builder_api_types::{
+    ForkVersionDecode, GetHeaderParams, SignedBlindedBeaconBlock, VersionedResponse,
+    CONSENSUS_VERSION_HEADER,
 };
 use bytes::Bytes;
-use http::{header::CONTENT_TYPE, HeaderValue, StatusCode};
+use http::{
+    header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE},
+    HeaderMap, HeaderValue, StatusCode,
+};
 use relay_api_types::{
-    GetDeliveredPayloadsQueryParams, GetReceivedBidsQueryParams,
-    GetValidatorRegistrationQueryParams, Response as RelayResponse, SubmitBlockQueryParams,
-    SubmitBlockRequest,
+    GetArchivedPayloadQueryParams, GetBuilderDemotionsQueryParams, GetDeliveredPayloadsQueryParams,
+    GetReceivedBidsQueryParams, GetValidatorRegistrationQueryParams, Response as RelayResponse,
+    SubmitBlockQueryParams, SubmitBlockRequest, UpdateBuilderRequest,
 };
 use serde::Serialize;
+use ssz::Encode;
 use tracing::error;
-use types::eth_spec::EthSpec;
+use types::{eth_spec::EthSpec, ForkName, PublicKeyBytes, SignedValidatorRegistrationData};
+
+use crate::{
+    admin::BuilderRegistry, archive::Archive, builder::Builder, data::Data, metrics::Metrics,
+    proposer::Proposer,
+};
 
-use crate::{builder::Builder, data::Data};
+/// Header carrying the submitting builder's API key.
+pub const API_KEY_HEADER: &str = "X-Api-Key";
 
-/// Setup API Server.
+const JSON_CONTENT_TYPE: &str = "application/json";
+const SSZ_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// Setup API Server serving both the Builder and Data APIs from one implementation.
 pub fn new<I, A, E>(api_impl: I) -> Router
 where
     E: EthSpec,
     I: AsRef<A> + Clone + Send + Sync + 'static,
     A: Builder<E> + Data + 'static,
 {
-    // build our application with a route
+    RouterBuilder::new()
+        .builder_api::<I, A, E>(api_impl.clone())
+        .data_api::<I, A>(api_impl)
+        .build()
+}
+
+/// Setup a router serving only the Builder API.
+pub fn builder_router<I, A, E>(api_impl: I) -> Router
+where
+    E: EthSpec,
+    I: AsRef<A> + Clone + Send + Sync + 'static,
+    A: Builder<E> + 'static,
+{
     Router::new()
         .route("/relay/v1/builder/blocks", post(submit_block::<I, A, E>))
         .route(
             "/relay/v1/builder/validators",
             get(get_validators::<I, A, E>),
         )
+        .with_state(api_impl)
+}
+
+/// Setup a router serving only the Builder API, only accepting submissions that carry the
+/// submitting builder's API key in the [`API_KEY_HEADER`] header.
+pub fn authenticated_builder_router<I, A, E>(api_impl: I) -> Router
+where
+    E: EthSpec,
+    I: AsRef<A> + Clone + Send + Sync + 'static,
+    A: Builder<E> + BuilderRegistry + 'static,
+{
+    Router::new()
+        .route(
+            "/relay/v1/builder/blocks",
+            post(submit_authenticated_block::<I, A, E>),
+        )
+        .route(
+            "/relay/v1/builder/validators",
+            get(get_validators::<I, A, E>),
+        )
+        .with_state(api_impl)
+}
+
+/// Setup a router serving only the Data API.
+pub fn data_router<I, A>(api_impl: I) -> Router
+where
+    I: AsRef<A> + Clone + Send + Sync + 'static,
+    A: Data + 'static,
+{
+    Router::new()
         .route(
             "/relay/v1/data/bidtraces/builder_blocks_received",
             get(get_received_bids::<I, A>),
@@ -45,9 +107,196 @@ where
             "/relay/v1/data/validator_registration",
             get(get_validator_registration::<I, A>),
         )
+        .route(
+            "/relay/v1/data/builder_demotions",
+            get(get_builder_demotions::<I, A>),
+        )
+        .with_state(api_impl)
+}
+
+/// Setup a router serving archived payloads.
+pub fn archive_router<I, A, E>(api_impl: I) -> Router
+where
+    E: EthSpec,
+    I: AsRef<A> + Clone + Send + Sync + 'static,
+    A: Archive<E> + 'static,
+{
+    Router::new()
+        .route(
+            "/relay/v1/data/archive/payload",
+            get(get_archived_payload::<I, A, E>),
+        )
+        .with_state(api_impl)
+}
+
+/// Setup a router serving the Builder API to proposers.
+pub fn proposer_router<I, A, E>(api_impl: I) -> Router
+where
+    E: EthSpec,
+    I: AsRef<A> + Clone + Send + Sync + 'static,
+    A: Proposer<E> + 'static,
+{
+    Router::new()
+        .route(
+            "/eth/v1/builder/validators",
+            post(register_validators::<I, A, E>),
+        )
+        .route(
+            "/eth/v1/builder/header/:slot/:parent_hash/:pubkey",
+            get(get_header::<I, A, E>),
+        )
+        .route(
+            "/eth/v1/builder/blinded_blocks",
+            post(submit_blinded_block::<I, A, E>),
+        )
+        .route("/eth/v1/builder/status", get(status::<I, A, E>))
+        .with_state(api_impl)
+}
+
+/// Setup a router serving the Admin API. Every request must carry an
+/// `Authorization: Bearer <admin_token>` header.
+pub fn admin_router<I, A>(api_impl: I, admin_token: String) -> Router
+where
+    I: AsRef<A> + Clone + Send + Sync + 'static,
+    A: BuilderRegistry + 'static,
+{
+    Router::new()
+        .route("/relay/v1/admin/builders", get(list_builders::<I, A>))
+        .route(
+            "/relay/v1/admin/builders/:builder_pubkey",
+            get(get_builder::<I, A>).post(update_builder::<I, A>),
+        )
+        .route(
+            "/relay/v1/admin/builders/:builder_pubkey/api_key",
+            post(rotate_api_key::<I, A>),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            Arc::<str>::from(admin_token),
+            require_admin_token,
+        ))
         .with_state(api_impl)
 }
 
+/// A path prefix routes cannot be mounted under. Prefixes must start with `/` and cannot contain
+/// wildcards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidPrefix(pub String);
+
+impl std::fmt::Display for InvalidPrefix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid path prefix {:?}, expected e.g. \"/relay-a\"",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for InvalidPrefix {}
+
+/// Composes the relay API routers with custom routes, optionally mounted under a path prefix.
+#[derive(Default)]
+pub struct RouterBuilder {
+    router: Router,
+    prefix: Option<String>,
+}
+
+impl RouterBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serve the Builder API from `api_impl`.
+    pub fn builder_api<I, A, E>(self, api_impl: I) -> Self
+    where
+        E: EthSpec,
+        I: AsRef<A> + Clone + Send + Sync + 'static,
+        A: Builder<E> + 'static,
+    {
+        self.merge(builder_router::<I, A, E>(api_impl))
+    }
+
+    /// Serve the Builder API from `api_impl`, see [`authenticated_builder_router`].
+    pub fn authenticated_builder_api<I, A, E>(self, api_impl: I) -> Self
+    where
+        E: EthSpec,
+        I: AsRef<A> + Clone + Send + Sync + 'static,
+        A: Builder<E> + BuilderRegistry + 'static,
+    {
+        self.merge(authenticated_builder_router::<I, A, E>(api_impl))
+    }
+
+    /// Serve the Data API from `api_impl`.
+    pub fn data_api<I, A>(self, api_impl: I) -> Self
+    where
+        I: AsRef<A> + Clone + Send + Sync + 'static,
+        A: Data + 'static,
+    {
+        self.merge(data_router::<I, A>(api_impl))
+    }
+
+    /// Serve archived payloads from `api_impl`.
+    pub fn archive_api<I, A, E>(self, api_impl: I) -> Self
+    where
+        E: EthSpec,
+        I: AsRef<A> + Clone + Send + Sync + 'static,
+        A: Archive<E> + 'static,
+    {
+        self.merge(archive_router::<I, A, E>(api_impl))
+    }
+
+    /// Serve the Builder API to proposers from `api_impl`.
+    pub fn proposer_api<I, A, E>(self, api_impl: I) -> Self
+    where
+        E: EthSpec,
+        I: AsRef<A> + Clone + Send + Sync + 'static,
+        A: Proposer<E> + 'static,
+    {
+        self.merge(proposer_router::<I, A, E>(api_impl))
+    }
+
+    /// Serve the Admin API from `api_impl`, see [`admin_router`].
+    pub fn admin_api<I, A>(self, api_impl: I, admin_token: String) -> Self
+    where
+        I: AsRef<A> + Clone + Send + Sync + 'static,
+        A: BuilderRegistry + 'static,
+    {
+        self.merge(admin_router::<I, A>(api_impl, admin_token))
+    }
+
+    /// Add a custom route. The path is relative to the prefix, if one is set.
+    pub fn route(mut self, path: &str, method_router: MethodRouter) -> Self {
+        self.router = self.router.route(path, method_router);
+        self
+    }
+
+    /// Merge the routes of another router. Paths are relative to the prefix, if one is set.
+    pub fn merge(mut self, router: Router) -> Self {
+        self.router = self.router.merge(router);
+        self
+    }
+
+    /// Mount all routes under `prefix`, e.g. `/relay-a`. A prefix of `/` mounts them at the
+    /// root.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Result<Self, InvalidPrefix> {
+        let prefix = prefix.into();
+        if !prefix.starts_with('/') || prefix.contains('*') {
+            return Err(InvalidPrefix(prefix));
+        }
+        self.prefix = Some(prefix);
+        Ok(self)
+    }
+
+    pub fn build(self) -> Router {
+        match self.prefix {
+            Some(prefix) if !prefix.trim_end_matches('/').is_empty() => {
+                Router::new().nest(prefix.trim_end_matches('/'), self.router)
+            }
+            _ => self.router,
+        }
+    }
+}
+
 async fn build_response<T>(result: RelayResponse<T>) -> Result<Response<Body>, StatusCode>
 where
     T: Serialize + Send + 'static,
@@ -120,11 +369,89 @@ where
     resp
 }
 
+/// Whether the `Accept` header prefers SSZ over JSON. Without an `Accept` header JSON is used.
+fn prefers_ssz(headers: &HeaderMap) -> bool {
+    let Some(accept) = headers.get(ACCEPT).and_then(|value| value.to_str().ok()) else {
+        return false;
+    };
+    let quality = |media_type: &str| {
+        accept
+            .split(',')
+            .filter_map(|media_range| {
+                let mut params = media_range.split(';').map(str::trim);
+                if params.next()? != media_type {
+                    return None;
+                }
+                Some(
+                    params
+                        .find_map(|param| param.strip_prefix("q="))
+                        .and_then(|q| q.parse::<f32>().ok())
+                        .unwrap_or(1.0),
+                )
+            })
+            .reduce(f32::max)
+    };
+
+    match quality(SSZ_CONTENT_TYPE) {
+        Some(ssz) => ssz > 0.0 && quality(JSON_CONTENT_TYPE).is_none_or(|json| ssz >= json),
+        None => false,
+    }
+}
+
+/// Build a response with the fork in the `Eth-Consensus-Version` header, encoded as SSZ if
+/// `ssz` is set and as JSON otherwise.
+async fn build_versioned_response<T>(
+    result: RelayResponse<VersionedResponse<T>>,
+    ssz: bool,
+) -> Result<Response<Body>, StatusCode>
+where
+    T: Serialize + Encode + Send + 'static,
+{
+    let response = match result {
+        RelayResponse::Success(response) => response,
+        RelayResponse::Error(body) => {
+            return build_response(RelayResponse::<()>::Error(body)).await
+        }
+    };
+
+    let version = HeaderValue::from_str(&response.version.to_string()).map_err(|e| {
+        error!(error = ?e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let (content_type, body_content) = if ssz {
+        (SSZ_CONTENT_TYPE, response.data.as_ssz_bytes())
+    } else {
+        let body_content = tokio::task::spawn_blocking(move || {
+            serde_json::to_vec(&response).map_err(|e| {
+                error!(error = ?e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })
+        })
+        .await
+        .map_err(|e| {
+            error!(error = ?e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })??;
+        (JSON_CONTENT_TYPE, body_content)
+    };
+
+    Response::builder()
+        .status(200)
+        .header(CONTENT_TYPE, content_type)
+        .header(CONSENSUS_VERSION_HEADER, version)
+        .body(Body::from(body_content))
+        .map_err(|e| {
+            error!(error = ?e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
 /// SubmitBlock - POST /relay/v1/builder/blocks
 #[tracing::instrument(skip_all)]
 async fn submit_block<I, A, E>(
     Query(query_params): Query<SubmitBlockQueryParams>,
     State(api_impl): State<I>,
+    metrics: Option<Extension<Metrics>>,
     JsonOrSsz(body): JsonOrSsz<SubmitBlockRequest<E>>,
 ) -> Result<Response<Body>, StatusCode>
 where
@@ -132,10 +459,43 @@ where
     I: AsRef<A> + Send + Sync,
     A: Builder<E>,
 {
+    if let Some(Extension(metrics)) = metrics {
+        metrics.observe_submission(&body);
+    }
     let result = api_impl.as_ref().submit_block(query_params, body).await;
     build_response(result).await
 }
 
+/// SubmitBlock - POST /relay/v1/builder/blocks, rejecting submissions without the builder's API
+/// key.
+#[tracing::instrument(skip_all)]
+async fn submit_authenticated_block<I, A, E>(
+    headers: HeaderMap,
+    query_params: Query<SubmitBlockQueryParams>,
+    State(api_impl): State<I>,
+    metrics: Option<Extension<Metrics>>,
+    JsonOrSsz(body): JsonOrSsz<SubmitBlockRequest<E>>,
+) -> Result<Response<Body>, StatusCode>
+where
+    E: EthSpec,
+    I: AsRef<A> + Send + Sync,
+    A: Builder<E> + BuilderRegistry,
+{
+    let authorized = headers
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|api_key| {
+            api_impl
+                .as_ref()
+                .verify_api_key(&body.message().builder_pubkey, api_key)
+        });
+
+    if !authorized {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    submit_block::<I, A, E>(query_params, State(api_impl), metrics, JsonOrSsz(body)).await
+}
+
 /// GetValidators - GET /relay/v1/builder/validators
 #[tracing::instrument(skip_all)]
 async fn get_validators<I, A, E>(State(api_impl): State<I>) -> Result<Response<Body>, StatusCode>
@@ -172,8 +532,11 @@ where
     I: AsRef<A> + Send + Sync,
     A: Data,
 {
-    let result = api_impl.as_ref().get_received_bids(query_params).await;
-    build_response(result).await
+    let api_impl = api_impl.as_ref();
+    match api_impl.get_received_bid_traces(query_params.clone()).await {
+        Some(result) => build_response(result).await,
+        None => build_response(api_impl.get_received_bids(query_params).await).await,
+    }
 }
 
 /// GetValidatorRegistration - GET /relay/v1/data/validator_registration
@@ -193,6 +556,179 @@ where
     build_response(result).await
 }
 
+/// GetBuilderDemotions - GET /relay/v1/data/builder_demotions
+#[tracing::instrument(skip_all)]
+async fn get_builder_demotions<I, A>(
+    Query(query_params): Query<GetBuilderDemotionsQueryParams>,
+    State(api_impl): State<I>,
+) -> Result<Response<Body>, StatusCode>
+where
+    I: AsRef<A> + Send + Sync,
+    A: Data,
+{
+    let result = api_impl.as_ref().get_builder_demotions(query_params).await;
+    build_response(result).await
+}
+
+/// GetArchivedPayload - GET /relay/v1/data/archive/payload
+#[tracing::instrument(skip_all)]
+async fn get_archived_payload<I, A, E>(
+    Query(query_params): Query<GetArchivedPayloadQueryParams>,
+    State(api_impl): State<I>,
+    headers: HeaderMap,
+) -> Result<Response<Body>, StatusCode>
+where
+    I: AsRef<A> + Send + Sync,
+    A: Archive<E>,
+    E: EthSpec,
+{
+    let result = api_impl.as_ref().get_archived_payload(query_params).await;
+    build_versioned_response(result, prefers_ssz(&headers)).await
+}
+
+/// RegisterValidators - POST /eth/v1/builder/validators
+#[tracing::instrument(skip_all)]
+async fn register_validators<I, A, E>(
+    State(api_impl): State<I>,
+    JsonOrSsz(body): JsonOrSsz<Vec<SignedValidatorRegistrationData>>,
+) -> Result<Response<Body>, StatusCode>
+where
+    I: AsRef<A> + Send + Sync,
+    A: Proposer<E>,
+    E: EthSpec,
+{
+    let result = api_impl.as_ref().register_validators(body).await;
+    build_response(result).await
+}
+
+/// GetHeader - GET /eth/v1/builder/header/{slot}/{parent_hash}/{pubkey}
+#[tracing::instrument(skip_all)]
+async fn get_header<I, A, E>(
+    Path(params): Path<GetHeaderParams>,
+    State(api_impl): State<I>,
+    headers: HeaderMap,
+) -> Result<Response<Body>, StatusCode>
+where
+    I: AsRef<A> + Send + Sync,
+    A: Proposer<E>,
+    E: EthSpec,
+{
+    let result = match api_impl.as_ref().get_header(params).await {
+        RelayResponse::Success(Some(response)) => RelayResponse::Success(response),
+        RelayResponse::Success(None) => return Ok(StatusCode::NO_CONTENT.into_response()),
+        RelayResponse::Error(body) => RelayResponse::Error(body),
+    };
+    build_versioned_response(result, prefers_ssz(&headers)).await
+}
+
+/// SubmitBlindedBlock - POST /eth/v1/builder/blinded_blocks
+#[tracing::instrument(skip_all)]
+async fn submit_blinded_block<I, A, E>(
+    State(api_impl): State<I>,
+    headers: HeaderMap,
+    ForkVersioned(body): ForkVersioned<SignedBlindedBeaconBlock<E>>,
+) -> Result<Response<Body>, StatusCode>
+where
+    I: AsRef<A> + Send + Sync,
+    A: Proposer<E>,
+    E: EthSpec,
+{
+    let result = api_impl.as_ref().submit_blinded_block(body).await;
+    build_versioned_response(result, prefers_ssz(&headers)).await
+}
+
+/// Status - GET /eth/v1/builder/status
+#[tracing::instrument(skip_all)]
+async fn status<I, A, E>(State(api_impl): State<I>) -> Result<Response<Body>, StatusCode>
+where
+    I: AsRef<A> + Send + Sync,
+    A: Proposer<E>,
+    E: EthSpec,
+{
+    match api_impl.as_ref().status().await {
+        RelayResponse::Success(()) => Ok(StatusCode::OK.into_response()),
+        result => build_response(result).await,
+    }
+}
+
+/// ListBuilders - GET /relay/v1/admin/builders
+#[tracing::instrument(skip_all)]
+async fn list_builders<I, A>(State(api_impl): State<I>) -> Result<Response<Body>, StatusCode>
+where
+    I: AsRef<A> + Send + Sync,
+    A: BuilderRegistry,
+{
+    let result = api_impl.as_ref().list_builders().await;
+    build_response(result).await
+}
+
+/// GetBuilder - GET /relay/v1/admin/builders/{builder_pubkey}
+#[tracing::instrument(skip_all)]
+async fn get_builder<I, A>(
+    Path(builder_pubkey): Path<PublicKeyBytes>,
+    State(api_impl): State<I>,
+) -> Result<Response<Body>, StatusCode>
+where
+    I: AsRef<A> + Send + Sync,
+    A: BuilderRegistry,
+{
+    let result = api_impl.as_ref().get_builder(builder_pubkey).await;
+    build_response(result).await
+}
+
+/// UpdateBuilder - POST /relay/v1/admin/builders/{builder_pubkey}
+#[tracing::instrument(skip_all)]
+async fn update_builder<I, A>(
+    Path(builder_pubkey): Path<PublicKeyBytes>,
+    State(api_impl): State<I>,
+    Json(body): Json<UpdateBuilderRequest>,
+) -> Result<Response<Body>, StatusCode>
+where
+    I: AsRef<A> + Send + Sync,
+    A: BuilderRegistry,
+{
+    let result = api_impl.as_ref().update_builder(builder_pubkey, body).await;
+    build_response(result).await
+}
+
+/// RotateApiKey - POST /relay/v1/admin/builders/{builder_pubkey}/api_key
+#[tracing::instrument(skip_all)]
+async fn rotate_api_key<I, A>(
+    Path(builder_pubkey): Path<PublicKeyBytes>,
+    State(api_impl): State<I>,
+) -> Result<Response<Body>, StatusCode>
+where
+    I: AsRef<A> + Send + Sync,
+    A: BuilderRegistry,
+{
+    let result = api_impl.as_ref().rotate_api_key(builder_pubkey).await;
+    build_response(result).await
+}
+
+async fn require_admin_token(
+    State(admin_token): State<Arc<str>>,
+    req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let authorized = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| {
+            !admin_token.is_empty() && constant_time_eq(token.as_bytes(), admin_token.as_bytes())
+        });
+
+    if !authorized {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    Ok(next.run(req).await)
+}
+
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
 #[must_use]
 #[derive(Debug, Clone, Copy, Default)]
 struct Ssz<T>(T);
@@ -211,12 +747,16 @@ where
 
         if let Some(content_type) = content_type {
             if content_type.starts_with("application/octet-stream") {
+                let metrics = req.extensions().get::<Metrics>().cloned();
                 let bytes = Bytes::from_request(req, state)
                     .await
                     .map_err(IntoResponse::into_response)?;
-                return Ok(T::from_ssz_bytes(&bytes)
-                    .map(Ssz)
-                    .map_err(|_| StatusCode::BAD_REQUEST.into_response())?);
+                return T::from_ssz_bytes(&bytes).map(Ssz).map_err(|_| {
+                    if let Some(metrics) = metrics {
+                        metrics.observe_decode_failure("ssz");
+                    }
+                    StatusCode::BAD_REQUEST.into_response()
+                });
             }
         }
 
@@ -240,9 +780,16 @@ where
         let content_type_header = req.headers().get(CONTENT_TYPE);
         let content_type = content_type_header.and_then(|value| value.to_str().ok());
 
+        let metrics = req.extensions().get::<Metrics>().cloned();
+
         if let Some(content_type) = content_type {
             if content_type.starts_with("application/json") {
-                let Json(payload) = req.extract().await.map_err(IntoResponse::into_response)?;
+                let Json(payload) = req.extract().await.map_err(|rejection| {
+                    if let Some(metrics) = &metrics {
+                        metrics.observe_decode_failure("json");
+                    }
+                    rejection.into_response()
+                })?;
                 return Ok(Self(payload));
             }
 
@@ -252,6 +799,165 @@ where
             }
         }
 
+        if let Some(metrics) = metrics {
+            metrics.observe_decode_failure("unsupported");
+        }
         Err(StatusCode::UNSUPPORTED_MEDIA_TYPE.into_response())
     }
 }
+
+/// A JSON or SSZ body whose fork is given by the `Eth-Consensus-Version` header.
+#[must_use]
+#[derive(Debug, Clone, Copy, Default)]
+struct ForkVersioned<T>(T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ForkVersioned<T>
+where
+    T: ForkVersionDecode,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Some(fork_name) = req
+            .headers()
+            .get(CONSENSUS_VERSION_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| ForkName::from_str(value).ok())
+        else {
+            return Err(StatusCode::BAD_REQUEST.into_response());
+        };
+        let content_type = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or(JSON_CONTENT_TYPE)
+            .to_owned();
+
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        if content_type.starts_with(JSON_CONTENT_TYPE) {
+            return serde_json::from_slice(&bytes)
+                .and_then(|value| T::from_json_value_by_fork(value, fork_name))
+                .map(Self)
+                .map_err(|_| StatusCode::BAD_REQUEST.into_response());
+        }
+        if content_type.starts_with(SSZ_CONTENT_TYPE) {
+            return T::from_ssz_bytes_by_fork(&bytes, fork_name)
+                .map(Self)
+                .map_err(|_| StatusCode::BAD_REQUEST.into_response());
+        }
+
+        Err(StatusCode::UNSUPPORTED_MEDIA_TYPE.into_response())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tower::ServiceExt;
+    use types::{MainnetEthSpec, Slot};
+
+    use super::*;
+    use crate::{
+        archive::MemoryPayloadArchive,
+        in_memory::InMemoryRelay,
+        test_utils::{block_hash, capella_submission, pubkey, registration, trace},
+    };
+
+    type E = MainnetEthSpec;
+
+    #[tokio::test]
+    async fn submission_api_key() {
+        let relay = Arc::new(InMemoryRelay::<E>::new());
+        let RelayResponse::Success(key) = relay.rotate_api_key(pubkey(1)).await else {
+            panic!("no API key issued");
+        };
+        let router = authenticated_builder_router::<_, InMemoryRelay<E>, E>(relay.clone());
+        let body = serde_json::to_vec(&capella_submission::<E>(trace(10, 1, 2, 3, 100))).unwrap();
+
+        let submit = |api_key: Option<&str>| {
+            let mut request = http::Request::post("/relay/v1/builder/blocks")
+                .header(CONTENT_TYPE, JSON_CONTENT_TYPE);
+            if let Some(api_key) = api_key {
+                request = request.header(API_KEY_HEADER, api_key);
+            }
+            let request = request.body(Body::from(body.clone())).unwrap();
+            let router = router.clone();
+            async move { router.oneshot(request).await.unwrap().status() }
+        };
+
+        assert_eq!(submit(None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(submit(Some("wrong")).await, StatusCode::UNAUTHORIZED);
+        assert_ne!(submit(Some(&key.api_key)).await, StatusCode::UNAUTHORIZED);
+
+        // A rotated key replaces the previous one.
+        relay.rotate_api_key(pubkey(1)).await;
+        assert_eq!(submit(Some(&key.api_key)).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn archived_payload_version() {
+        let relay = InMemoryRelay::<E>::new().with_archive(Arc::new(MemoryPayloadArchive::new()));
+        relay.set_proposer_duty(Slot::new(10), 0, pubkey(2));
+        relay.register_validator(registration(2)).await.unwrap();
+        let submission = capella_submission::<E>(trace(10, 1, 2, 3, 100));
+        let query_params = SubmitBlockQueryParams {
+            cancellations: None,
+        };
+        assert_eq!(
+            relay.submit_block(query_params, submission).await,
+            RelayResponse::Success(())
+        );
+        relay.deliver_payload(block_hash(3)).await.unwrap();
+
+        let router = archive_router::<_, InMemoryRelay<E>, E>(Arc::new(relay));
+        let request = http::Request::get(format!(
+            "/relay/v1/data/archive/payload?block_hash={}",
+            block_hash(3)
+        ))
+        .body(Body::empty())
+        .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(CONSENSUS_VERSION_HEADER).unwrap(),
+            "capella"
+        );
+    }
+
+    #[test]
+    fn router_prefix() {
+        for prefix in ["", "relay-a", "/relay-a/*rest"] {
+            assert_eq!(
+                RouterBuilder::new().prefix(prefix).err(),
+                Some(InvalidPrefix(prefix.to_owned()))
+            );
+        }
+        for prefix in ["/", "/relay-a", "/relay-a/"] {
+            let _ = RouterBuilder::new().prefix(prefix).unwrap().build();
+        }
+    }
+
+    #[test]
+    fn accept_negotiation() {
+        let accept = |value: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(ACCEPT, HeaderValue::from_static(value));
+            prefers_ssz(&headers)
+        };
+
+        assert!(!prefers_ssz(&HeaderMap::new()));
+        assert!(!accept("application/json"));
+        assert!(accept("application/octet-stream"));
+        assert!(accept(
+            "application/octet-stream;q=1.0,application/json;q=0.9"
+        ));
+        assert!(!accept(
+            "application/octet-stream;q=0.5,application/json;q=0.9"
+        ));
+        assert!(!accept("application/octet-stream;q=0"));
+    }
+}
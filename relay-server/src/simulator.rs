@@ -0,0 +1,401 @@
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use relay_api_types::{
+    SubmitBlockRequest, SubmitBlockRequestBellatrix, SubmitBlockRequestCapella,
+    SubmitBlockRequestDeneb, SubmitBlockRequestElectra,
+};
+use reqwest::Client;
+use serde::{Deserialize, Deserializer, Serialize};
+use tree_hash::TreeHash;
+use types::{eth_spec::EthSpec, Hash256};
+
+/// Default time an execution client is given to simulate a block.
+pub const DEFAULT_SIMULATION_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// JSON-RPC error code used by [`MockSimulator`] for invalid blocks.
+pub const MOCK_INVALID_BLOCK_CODE: i64 = -32000;
+
+/// Whether a JSON-RPC error `code` reports a block failing validation, as opposed to the
+/// execution client being unable to validate it: a malformed request, an unknown method or an
+/// internal error.
+pub fn is_invalid_block_code(code: i64) -> bool {
+    !matches!(code, -32700 | -32603..=-32600)
+}
+
+#[derive(Debug)]
+pub enum SimulationError {
+    Reqwest(reqwest::Error),
+    Timeout,
+    InvalidResponse(String),
+    /// The execution client rejected the block.
+    Rpc {
+        code: i64,
+        message: String,
+    },
+    /// Deneb and later simulations need the parent beacon block root.
+    MissingParentBeaconBlockRoot,
+}
+
+impl From<reqwest::Error> for SimulationError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            SimulationError::Timeout
+        } else {
+            SimulationError::Reqwest(e)
+        }
+    }
+}
+
+/// Simulates submitted blocks before a relay serves them to proposers.
+#[async_trait]
+pub trait BlockSimulator<E: EthSpec>: Send + Sync {
+    /// Returns `Ok(())` if the block is valid and pays the proposer as claimed in its bid trace.
+    async fn simulate(&self, request: &BlockSimulationRequest<E>) -> Result<(), SimulationError>;
+}
+
+/// flashbots_validateBuilderSubmissionV1 params.
+#[derive(Debug, Clone, Serialize)]
+#[serde(bound = "E: EthSpec")]
+pub struct BlockSimulationRequestV1<E: EthSpec> {
+    #[serde(flatten)]
+    pub submission: SubmitBlockRequestBellatrix<E>,
+    #[serde(with = "serde_utils::quoted_u64")]
+    pub registered_gas_limit: u64,
+}
+
+/// flashbots_validateBuilderSubmissionV2 params.
+#[derive(Debug, Clone, Serialize)]
+#[serde(bound = "E: EthSpec")]
+pub struct BlockSimulationRequestV2<E: EthSpec> {
+    #[serde(flatten)]
+    pub submission: SubmitBlockRequestCapella<E>,
+    #[serde(with = "serde_utils::quoted_u64")]
+    pub registered_gas_limit: u64,
+    pub withdrawals_root: Hash256,
+}
+
+/// flashbots_validateBuilderSubmissionV3 params.
+#[derive(Debug, Clone, Serialize)]
+#[serde(bound = "E: EthSpec")]
+pub struct BlockSimulationRequestV3<E: EthSpec> {
+    #[serde(flatten)]
+    pub submission: SubmitBlockRequestDeneb<E>,
+    pub parent_beacon_block_root: Hash256,
+    #[serde(with = "serde_utils::quoted_u64")]
+    pub registered_gas_limit: u64,
+}
+
+/// flashbots_validateBuilderSubmissionV4 params.
+#[derive(Debug, Clone, Serialize)]
+#[serde(bound = "E: EthSpec")]
+pub struct BlockSimulationRequestV4<E: EthSpec> {
+    #[serde(flatten)]
+    pub submission: SubmitBlockRequestElectra<E>,
+    pub parent_beacon_block_root: Hash256,
+    #[serde(with = "serde_utils::quoted_u64")]
+    pub registered_gas_limit: u64,
+}
+
+/// A block simulation request, shaped like the params of the
+/// `flashbots_validateBuilderSubmissionV*` call matching the submission's fork.
+#[derive(Debug, Clone, Serialize)]
+#[serde(bound = "E: EthSpec", untagged)]
+pub enum BlockSimulationRequest<E: EthSpec> {
+    V1(BlockSimulationRequestV1<E>),
+    V2(BlockSimulationRequestV2<E>),
+    V3(BlockSimulationRequestV3<E>),
+    V4(BlockSimulationRequestV4<E>),
+}
+
+impl<E: EthSpec> BlockSimulationRequest<E> {
+    /// `registered_gas_limit` is the gas limit from the proposer's registration.
+    /// `parent_beacon_block_root` is required for Deneb and later submissions.
+    pub fn new(
+        submission: SubmitBlockRequest<E>,
+        registered_gas_limit: u64,
+        parent_beacon_block_root: Option<Hash256>,
+    ) -> Result<Self, SimulationError> {
+        let request = match submission {
+            SubmitBlockRequest::Bellatrix(submission) => Self::V1(BlockSimulationRequestV1 {
+                submission,
+                registered_gas_limit,
+            }),
+            SubmitBlockRequest::Capella(submission) => Self::V2(BlockSimulationRequestV2 {
+                withdrawals_root: submission.execution_payload.withdrawals.tree_hash_root(),
+                submission,
+                registered_gas_limit,
+            }),
+            SubmitBlockRequest::Deneb(submission) => Self::V3(BlockSimulationRequestV3 {
+                submission,
+                parent_beacon_block_root: parent_beacon_block_root
+                    .ok_or(SimulationError::MissingParentBeaconBlockRoot)?,
+                registered_gas_limit,
+            }),
+            SubmitBlockRequest::Electra(submission) => Self::V4(BlockSimulationRequestV4 {
+                submission,
+                parent_beacon_block_root: parent_beacon_block_root
+                    .ok_or(SimulationError::MissingParentBeaconBlockRoot)?,
+                registered_gas_limit,
+            }),
+        };
+        Ok(request)
+    }
+
+    /// The JSON-RPC method validating this request.
+    pub fn method(&self) -> &'static str {
+        match self {
+            Self::V1(_) => "flashbots_validateBuilderSubmissionV1",
+            Self::V2(_) => "flashbots_validateBuilderSubmissionV2",
+            Self::V3(_) => "flashbots_validateBuilderSubmissionV3",
+            Self::V4(_) => "flashbots_validateBuilderSubmissionV4",
+        }
+    }
+
+    pub fn into_submission(self) -> SubmitBlockRequest<E> {
+        match self {
+            Self::V1(request) => SubmitBlockRequest::Bellatrix(request.submission),
+            Self::V2(request) => SubmitBlockRequest::Capella(request.submission),
+            Self::V3(request) => SubmitBlockRequest::Deneb(request.submission),
+            Self::V4(request) => SubmitBlockRequest::Electra(request.submission),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonRpcRequest<'a, T> {
+    jsonrpc: &'static str,
+    id: u64,
+    method: &'a str,
+    params: [&'a T; 1],
+}
+
+#[derive(Deserialize)]
+struct JsonRpcResponse {
+    /// `Some` for a `null` result too, which is how valid blocks are reported.
+    #[serde(default, deserialize_with = "present")]
+    result: Option<serde_json::Value>,
+    #[serde(default)]
+    error: Option<JsonRpcError>,
+}
+
+fn present<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<serde_json::Value>, D::Error> {
+    serde_json::Value::deserialize(deserializer).map(Some)
+}
+
+/// Interpret the body of a `flashbots_validateBuilderSubmissionV*` response.
+fn parse_response(status: reqwest::StatusCode, text: &str) -> Result<(), SimulationError> {
+    let invalid = || SimulationError::InvalidResponse(format!("{status}: {text}"));
+    let response: JsonRpcResponse = serde_json::from_str(text).map_err(|_| invalid())?;
+    match (response.error, response.result) {
+        (Some(error), _) => Err(SimulationError::Rpc {
+            code: error.code,
+            message: error.message,
+        }),
+        (None, Some(_)) => Ok(()),
+        (None, None) => Err(invalid()),
+    }
+}
+
+#[derive(Deserialize)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+/// Simulates blocks by calling `flashbots_validateBuilderSubmissionV*` on an execution client.
+pub struct JsonRpcSimulator {
+    client: Client,
+    url: String,
+    timeout: Duration,
+}
+
+impl JsonRpcSimulator {
+    pub fn new(url: String) -> Self {
+        Self::with_timeout(url, DEFAULT_SIMULATION_TIMEOUT)
+    }
+
+    pub fn with_timeout(url: String, timeout: Duration) -> Self {
+        Self {
+            client: Client::new(),
+            url,
+            timeout,
+        }
+    }
+}
+
+#[async_trait]
+impl<E: EthSpec> BlockSimulator<E> for JsonRpcSimulator {
+    async fn simulate(&self, request: &BlockSimulationRequest<E>) -> Result<(), SimulationError> {
+        let body = JsonRpcRequest {
+            jsonrpc: "2.0",
+            id: 1,
+            method: request.method(),
+            params: [request],
+        };
+        let response = self
+            .client
+            .post(&self.url)
+            .timeout(self.timeout)
+            .json(&body)
+            .send()
+            .await?;
+
+        let status = response.status();
+        let text = response.text().await?;
+        parse_response(status, &text)
+    }
+}
+
+/// A simulator returning a fixed result, for tests.
+#[derive(Debug, Default)]
+pub struct MockSimulator {
+    /// JSON-RPC error code and message returned for every block, or `None` to accept all blocks.
+    error: Option<(i64, String)>,
+    delay: Duration,
+    calls: AtomicUsize,
+}
+
+impl MockSimulator {
+    /// A simulator accepting every block.
+    pub fn valid() -> Self {
+        Self::default()
+    }
+
+    /// A simulator rejecting every block with `reason`.
+    pub fn invalid(reason: impl Into<String>) -> Self {
+        Self::rpc_error(MOCK_INVALID_BLOCK_CODE, reason)
+    }
+
+    /// A simulator failing every call with the JSON-RPC error `code` and `message`.
+    pub fn rpc_error(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            error: Some((code, message.into())),
+            ..Self::default()
+        }
+    }
+
+    /// Wait `delay` before returning each result.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Number of simulations requested so far.
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::Relaxed)
+    }
+}
+
+#[async_trait]
+impl<E: EthSpec> BlockSimulator<E> for MockSimulator {
+    async fn simulate(&self, _request: &BlockSimulationRequest<E>) -> Result<(), SimulationError> {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        if !self.delay.is_zero() {
+            tokio::time::sleep(self.delay).await;
+        }
+        match &self.error {
+            Some((code, message)) => Err(SimulationError::Rpc {
+                code: *code,
+                message: message.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+    use types::MainnetEthSpec;
+
+    use super::*;
+    use crate::test_utils::{capella_submission, deneb_submission, trace};
+
+    type E = MainnetEthSpec;
+
+    #[test]
+    fn json_rpc_response() {
+        let parse = |text| parse_response(StatusCode::OK, text);
+
+        assert!(parse(r#"{"jsonrpc":"2.0","id":1,"result":null}"#).is_ok());
+        assert!(matches!(
+            parse(r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32000,"message":"bad"}}"#),
+            Err(SimulationError::Rpc { code: -32000, .. })
+        ));
+        assert!(matches!(
+            parse(r#"{"jsonrpc":"2.0","id":1}"#),
+            Err(SimulationError::InvalidResponse(_))
+        ));
+        assert!(matches!(
+            parse("Bad Gateway"),
+            Err(SimulationError::InvalidResponse(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_block_codes() {
+        for code in [MOCK_INVALID_BLOCK_CODE, -32001, -38003, 1] {
+            assert!(is_invalid_block_code(code), "{code}");
+        }
+        for code in [-32700, -32600, -32601, -32602, -32603] {
+            assert!(!is_invalid_block_code(code), "{code}");
+        }
+    }
+
+    #[test]
+    fn request_encoding() {
+        let root = Hash256::repeat_byte(7);
+        let submission = deneb_submission::<E>(trace(1, 1, 2, 3, 100));
+
+        assert!(matches!(
+            BlockSimulationRequest::new(submission.clone(), 30_000_000, None),
+            Err(SimulationError::MissingParentBeaconBlockRoot)
+        ));
+
+        let request = BlockSimulationRequest::new(submission, 30_000_000, Some(root)).unwrap();
+        assert_eq!(request.method(), "flashbots_validateBuilderSubmissionV3");
+        let json = serde_json::to_value(&request).unwrap();
+        for field in ["message", "execution_payload", "blobs_bundle", "signature"] {
+            assert!(json.get(field).is_some(), "missing {field}");
+        }
+        assert_eq!(json["registered_gas_limit"], "30000000");
+        assert_eq!(json["parent_beacon_block_root"], format!("{root:?}"));
+
+        let request =
+            BlockSimulationRequest::new(capella_submission::<E>(trace(1, 1, 2, 3, 100)), 1, None)
+                .unwrap();
+        assert_eq!(request.method(), "flashbots_validateBuilderSubmissionV2");
+        assert!(serde_json::to_value(&request)
+            .unwrap()
+            .get("withdrawals_root")
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn mock_simulator() {
+        let request =
+            BlockSimulationRequest::new(capella_submission::<E>(trace(1, 1, 2, 3, 100)), 1, None)
+                .unwrap();
+
+        let valid = MockSimulator::valid();
+        assert!(BlockSimulator::<E>::simulate(&valid, &request)
+            .await
+            .is_ok());
+
+        let invalid = MockSimulator::invalid("bad block");
+        match BlockSimulator::<E>::simulate(&invalid, &request).await {
+            Err(SimulationError::Rpc { code, message }) => {
+                assert_eq!(code, MOCK_INVALID_BLOCK_CODE);
+                assert_eq!(message, "bad block");
+            }
+            result => panic!("unexpected result {result:?}"),
+        }
+        assert_eq!((valid.calls(), invalid.calls()), (1, 1));
+    }
+}
@@ -0,0 +1,181 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use types::{ChainSpec, Slot};
+
+/// Default number of slots ahead of the current slot a submission may be for.
+pub const DEFAULT_MAX_FUTURE_SLOTS: u64 = 1;
+/// Default time into its slot after which a submission is rejected.
+pub const DEFAULT_SUBMISSION_CUTOFF: Duration = Duration::from_secs(4);
+
+/// Maps wall-clock time to slots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotClock {
+    genesis_time: Duration,
+    slot_duration: Duration,
+}
+
+impl SlotClock {
+    /// `genesis_time` is in seconds since the Unix epoch.
+    pub fn new(genesis_time: u64, seconds_per_slot: u64) -> Self {
+        Self {
+            genesis_time: Duration::from_secs(genesis_time),
+            slot_duration: Duration::from_secs(seconds_per_slot),
+        }
+    }
+
+    pub fn from_spec(spec: &ChainSpec, genesis_time: u64) -> Self {
+        Self::new(genesis_time, spec.seconds_per_slot)
+    }
+
+    /// Time since the Unix epoch.
+    pub fn now_duration() -> Duration {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+    }
+
+    /// The current slot, or `None` before genesis.
+    pub fn now(&self) -> Option<Slot> {
+        self.slot_of(Self::now_duration())
+    }
+
+    /// The slot `timestamp` falls in, or `None` before genesis.
+    pub fn slot_of(&self, timestamp: Duration) -> Option<Slot> {
+        let since_genesis = timestamp.checked_sub(self.genesis_time)?;
+        Some(Slot::new(
+            (since_genesis.as_millis() / self.slot_duration.as_millis().max(1)) as u64,
+        ))
+    }
+
+    /// Start of `slot` as time since the Unix epoch.
+    pub fn start_of(&self, slot: Slot) -> Duration {
+        self.genesis_time
+            + Duration::from_millis(self.slot_duration.as_millis() as u64 * slot.as_u64())
+    }
+
+    /// Milliseconds from the start of `slot` to `timestamp`. Negative if `timestamp` is before
+    /// the slot starts.
+    pub fn ms_into_slot(&self, slot: Slot, timestamp: Duration) -> i64 {
+        timestamp.as_millis() as i64 - self.start_of(slot).as_millis() as i64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubmissionTimingError {
+    BeforeGenesis,
+    PastSlot {
+        slot: Slot,
+        current_slot: Slot,
+    },
+    FutureSlot {
+        slot: Slot,
+        current_slot: Slot,
+    },
+    /// The submission arrived after the cutoff into its slot.
+    TooLate {
+        slot: Slot,
+        ms_into_slot: i64,
+    },
+}
+
+/// Rejects submissions for slots that already passed or are too far ahead, using only the slot
+/// number of the bid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubmissionFilter {
+    pub clock: SlotClock,
+    /// Number of slots ahead of the current slot a submission may be for.
+    pub max_future_slots: u64,
+    /// Submissions for the current slot are rejected this long after it started.
+    pub cutoff: Duration,
+}
+
+impl SubmissionFilter {
+    pub fn new(clock: SlotClock) -> Self {
+        Self {
+            clock,
+            max_future_slots: DEFAULT_MAX_FUTURE_SLOTS,
+            cutoff: DEFAULT_SUBMISSION_CUTOFF,
+        }
+    }
+
+    /// Check a submission for `slot` received at `timestamp`, returning how far into `slot` it
+    /// was received.
+    pub fn check(&self, slot: Slot, timestamp: Duration) -> Result<i64, SubmissionTimingError> {
+        let current_slot = self
+            .clock
+            .slot_of(timestamp)
+            .ok_or(SubmissionTimingError::BeforeGenesis)?;
+
+        if slot < current_slot {
+            return Err(SubmissionTimingError::PastSlot { slot, current_slot });
+        }
+        if slot > current_slot + self.max_future_slots {
+            return Err(SubmissionTimingError::FutureSlot { slot, current_slot });
+        }
+
+        let ms_into_slot = self.clock.ms_into_slot(slot, timestamp);
+        if ms_into_slot > self.cutoff.as_millis() as i64 {
+            return Err(SubmissionTimingError::TooLate { slot, ms_into_slot });
+        }
+        Ok(ms_into_slot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENESIS: u64 = 1_000;
+
+    fn at(ms_since_genesis: u64) -> Duration {
+        Duration::from_secs(GENESIS) + Duration::from_millis(ms_since_genesis)
+    }
+
+    #[test]
+    fn slots() {
+        let clock = SlotClock::new(GENESIS, 12);
+
+        assert_eq!(clock.slot_of(Duration::from_secs(GENESIS - 1)), None);
+        assert_eq!(clock.slot_of(at(0)), Some(Slot::new(0)));
+        assert_eq!(clock.slot_of(at(11_999)), Some(Slot::new(0)));
+        assert_eq!(clock.slot_of(at(12_000)), Some(Slot::new(1)));
+        assert_eq!(clock.start_of(Slot::new(2)), at(24_000));
+        assert_eq!(clock.ms_into_slot(Slot::new(2), at(24_500)), 500);
+        assert_eq!(clock.ms_into_slot(Slot::new(2), at(23_000)), -1_000);
+    }
+
+    #[test]
+    fn submission_timing() {
+        let filter = SubmissionFilter::new(SlotClock::new(GENESIS, 12));
+        let slot = Slot::new(2);
+
+        assert_eq!(
+            filter.check(slot, Duration::from_secs(GENESIS - 1)),
+            Err(SubmissionTimingError::BeforeGenesis)
+        );
+        // During the previous slot, for the next one.
+        assert_eq!(filter.check(slot, at(23_000)), Ok(-1_000));
+        assert_eq!(filter.check(slot, at(28_000)), Ok(4_000));
+        assert_eq!(
+            filter.check(slot, at(28_001)),
+            Err(SubmissionTimingError::TooLate {
+                slot,
+                ms_into_slot: 4_001
+            })
+        );
+        assert_eq!(
+            filter.check(slot, at(36_000)),
+            Err(SubmissionTimingError::PastSlot {
+                slot,
+                current_slot: Slot::new(3)
+            })
+        );
+        assert_eq!(
+            filter.check(slot, at(0)),
+            Err(SubmissionTimingError::FutureSlot {
+                slot,
+                current_slot: Slot::new(0)
+            })
+        );
+    }
+}
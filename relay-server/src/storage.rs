@@ -0,0 +1,954 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap},
+    fs::{self, File, OpenOptions},
+    hash::Hash,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    iter,
+    ops::Bound,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use parking_lot::{Mutex, RwLock};
+use relay_api_types::{
+    BidTraceV1, BidTraceV2, BidTraceV2WithTimestamp, GetDeliveredPayloadsQueryParams,
+    GetReceivedBidsQueryParams, OrderBy, ReceivedBidTrace,
+};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use types::{ExecutionBlockHash, PublicKeyBytes, SignedValidatorRegistrationData, Slot};
+
+/// Name of the log file [`FileStorage`] keeps in its directory.
+pub const LOG_FILE_NAME: &str = "relay.log";
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Json(serde_json::Error),
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+/// A received bid or delivered payload as stored.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredBid {
+    pub trace: BidTraceV1,
+    /// When the relay received the bid or delivered the payload, in milliseconds since the Unix
+    /// epoch.
+    pub timestamp_ms: i64,
+    #[serde(default)]
+    pub ms_into_slot: Option<i64>,
+}
+
+impl StoredBid {
+    pub fn to_bid_trace_v2(&self) -> BidTraceV2 {
+        BidTraceV2 {
+            block_number: self.trace.block_number,
+            num_tx: self.trace.num_tx,
+            bid_trace: self.trace.clone(),
+        }
+    }
+
+    pub fn to_bid_trace_v2_with_timestamp(&self) -> BidTraceV2WithTimestamp {
+        BidTraceV2WithTimestamp {
+            bid_trace: self.to_bid_trace_v2(),
+            timestamp: self.timestamp_ms / 1000,
+            timestamp_ms: self.timestamp_ms,
+        }
+    }
+
+    pub fn to_received_bid_trace(&self) -> ReceivedBidTrace {
+        ReceivedBidTrace {
+            bid_trace: self.to_bid_trace_v2_with_timestamp(),
+            ms_into_slot: self.ms_into_slot,
+        }
+    }
+}
+
+/// Filters shared by the delivered payload and received bid queries. Every field that is set
+/// must match.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BidFilter {
+    pub slot: Option<Slot>,
+    /// Only bids for this slot or earlier.
+    pub cursor: Option<Slot>,
+    pub block_hash: Option<ExecutionBlockHash>,
+    pub block_number: Option<u64>,
+    pub proposer_pubkey: Option<PublicKeyBytes>,
+    pub builder_pubkey: Option<PublicKeyBytes>,
+}
+
+impl BidFilter {
+    pub fn matches(&self, trace: &BidTraceV1) -> bool {
+        self.slot.is_none_or(|slot| trace.slot == slot)
+            && self.cursor.is_none_or(|cursor| trace.slot <= cursor)
+            && self
+                .block_hash
+                .is_none_or(|block_hash| trace.block_hash == block_hash)
+            && self
+                .block_number
+                .is_none_or(|block_number| trace.block_number == block_number)
+            && self
+                .proposer_pubkey
+                .is_none_or(|pubkey| trace.proposer_pubkey == pubkey)
+            && self
+                .builder_pubkey
+                .is_none_or(|pubkey| trace.builder_pubkey == pubkey)
+    }
+}
+
+impl From<&GetDeliveredPayloadsQueryParams> for BidFilter {
+    fn from(query_params: &GetDeliveredPayloadsQueryParams) -> Self {
+        Self {
+            slot: query_params.slot,
+            cursor: query_params.cursor,
+            block_hash: query_params.block_hash,
+            block_number: query_params
+                .block_number
+                .as_ref()
+                .map(|block_number| block_number.value),
+            proposer_pubkey: query_params.proposer_pubkey,
+            builder_pubkey: query_params.builder_pubkey,
+        }
+    }
+}
+
+impl From<&GetReceivedBidsQueryParams> for BidFilter {
+    fn from(query_params: &GetReceivedBidsQueryParams) -> Self {
+        Self {
+            slot: query_params.slot,
+            block_hash: query_params.block_hash,
+            block_number: query_params
+                .block_number
+                .as_ref()
+                .map(|block_number| block_number.value),
+            builder_pubkey: query_params.builder_pubkey,
+            ..Self::default()
+        }
+    }
+}
+
+/// Storage behind the Data API: received bids, delivered payloads and validator registrations.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn insert_received_bid(&self, bid: StoredBid) -> Result<(), Error>;
+
+    async fn insert_delivered_payload(&self, payload: StoredBid) -> Result<(), Error>;
+
+    /// Store a validator registration, replacing any previous one for the same pubkey.
+    async fn insert_registration(
+        &self,
+        registration: SignedValidatorRegistrationData,
+    ) -> Result<(), Error>;
+
+    /// Up to `limit` received bids matching `filter`, most recently received first.
+    async fn received_bids(
+        &self,
+        filter: &BidFilter,
+        limit: usize,
+    ) -> Result<Vec<StoredBid>, Error>;
+
+    /// Up to `limit` delivered payloads matching `filter`, ordered by `order_by` or else by
+    /// descending slot.
+    async fn delivered_payloads(
+        &self,
+        filter: &BidFilter,
+        order_by: Option<OrderBy>,
+        limit: usize,
+    ) -> Result<Vec<StoredBid>, Error>;
+
+    async fn registration(
+        &self,
+        pubkey: &PublicKeyBytes,
+    ) -> Result<Option<SignedValidatorRegistrationData>, Error>;
+
+    /// Drop received bids and delivered payloads for slots before `slot`. Registrations are kept.
+    async fn prune(&self, slot: Slot) -> Result<(), Error>;
+}
+
+/// Rows are ordered by slot and then by insertion.
+type RowId = (Slot, u64);
+
+type RowIds<'a> = Box<dyn DoubleEndedIterator<Item = &'a RowId> + 'a>;
+
+/// Bids with an index for every field of [`BidFilter`].
+#[derive(Default)]
+struct Table {
+    rows: BTreeMap<RowId, StoredBid>,
+    next_id: u64,
+    by_block_hash: HashMap<ExecutionBlockHash, BTreeSet<RowId>>,
+    by_block_number: HashMap<u64, BTreeSet<RowId>>,
+    by_proposer: HashMap<PublicKeyBytes, BTreeSet<RowId>>,
+    by_builder: HashMap<PublicKeyBytes, BTreeSet<RowId>>,
+}
+
+fn index_range<'a, K: Eq + Hash>(
+    index: &'a HashMap<K, BTreeSet<RowId>>,
+    key: &K,
+    range: (Bound<RowId>, Bound<RowId>),
+) -> RowIds<'a> {
+    match index.get(key) {
+        Some(ids) => Box::new(ids.range(range)),
+        None => Box::new(iter::empty()),
+    }
+}
+
+fn prune_index<K>(index: &mut HashMap<K, BTreeSet<RowId>>, slot: Slot) {
+    index.retain(|_, ids| {
+        *ids = ids.split_off(&(slot, 0));
+        !ids.is_empty()
+    });
+}
+
+impl Table {
+    fn insert(&mut self, bid: StoredBid) {
+        let id = (bid.trace.slot, self.next_id);
+        self.next_id += 1;
+
+        let trace = &bid.trace;
+        self.by_block_hash
+            .entry(trace.block_hash)
+            .or_default()
+            .insert(id);
+        self.by_block_number
+            .entry(trace.block_number)
+            .or_default()
+            .insert(id);
+        self.by_proposer
+            .entry(trace.proposer_pubkey)
+            .or_default()
+            .insert(id);
+        self.by_builder
+            .entry(trace.builder_pubkey)
+            .or_default()
+            .insert(id);
+        self.rows.insert(id, bid);
+    }
+
+    /// Rows matching `filter` in descending order of slot and then insertion, scanning the most
+    /// selective index the filter allows.
+    fn select<'a>(&'a self, filter: &'a BidFilter) -> impl Iterator<Item = &'a StoredBid> + 'a {
+        let lower = match filter.slot {
+            Some(slot) => Bound::Included((slot, 0)),
+            None => Bound::Unbounded,
+        };
+        let upper = match filter.slot.into_iter().chain(filter.cursor).min() {
+            Some(slot) => Bound::Included((slot, u64::MAX)),
+            None => Bound::Unbounded,
+        };
+        let range = (lower, upper);
+
+        let ids: RowIds<'a> = if filter
+            .slot
+            .zip(filter.cursor)
+            .is_some_and(|(slot, cursor)| cursor < slot)
+        {
+            // An empty range, which `BTreeMap::range` would panic on.
+            Box::new(iter::empty())
+        } else if let Some(block_hash) = &filter.block_hash {
+            index_range(&self.by_block_hash, block_hash, range)
+        } else if let Some(block_number) = &filter.block_number {
+            index_range(&self.by_block_number, block_number, range)
+        } else if let Some(pubkey) = &filter.proposer_pubkey {
+            index_range(&self.by_proposer, pubkey, range)
+        } else if let Some(pubkey) = &filter.builder_pubkey {
+            index_range(&self.by_builder, pubkey, range)
+        } else {
+            Box::new(self.rows.range(range).map(|(id, _)| id))
+        };
+
+        ids.rev()
+            .filter_map(|id| self.rows.get(id))
+            .filter(|bid| filter.matches(&bid.trace))
+    }
+
+    fn prune(&mut self, slot: Slot) {
+        self.rows = self.rows.split_off(&(slot, 0));
+        prune_index(&mut self.by_block_hash, slot);
+        prune_index(&mut self.by_block_number, slot);
+        prune_index(&mut self.by_proposer, slot);
+        prune_index(&mut self.by_builder, slot);
+    }
+}
+
+/// A bid ranked by a key and then by its position among the bids selected.
+struct Ranked<'a, K> {
+    key: (K, usize),
+    bid: &'a StoredBid,
+}
+
+impl<K: Ord> PartialEq for Ranked<'_, K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<K: Ord> Eq for Ranked<'_, K> {}
+
+impl<K: Ord> PartialOrd for Ranked<'_, K> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord> Ord for Ranked<'_, K> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+/// The first `limit` of `bids` in ascending order of `key`, bids with equal keys staying in the
+/// order of `bids`. At most `limit` bids are kept while scanning.
+fn first_by_key<'a, K: Ord>(
+    bids: impl Iterator<Item = &'a StoredBid>,
+    limit: usize,
+    key: impl Fn(&StoredBid) -> K,
+) -> Vec<StoredBid> {
+    if limit == 0 {
+        return vec![];
+    }
+    let mut kept = BinaryHeap::new();
+    for (position, bid) in bids.enumerate() {
+        kept.push(Ranked {
+            key: (key(bid), position),
+            bid,
+        });
+        if kept.len() > limit {
+            kept.pop();
+        }
+    }
+    kept.into_sorted_vec()
+        .into_iter()
+        .map(|ranked| ranked.bid.clone())
+        .collect()
+}
+
+/// An entry of the [`FileStorage`] log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+enum Record {
+    ReceivedBid(StoredBid),
+    DeliveredPayload(StoredBid),
+    Registration(SignedValidatorRegistrationData),
+}
+
+#[derive(Default)]
+struct Index {
+    received: Table,
+    delivered: Table,
+    registrations: HashMap<PublicKeyBytes, SignedValidatorRegistrationData>,
+}
+
+impl Index {
+    fn apply(&mut self, record: Record) {
+        match record {
+            Record::ReceivedBid(bid) => self.received.insert(bid),
+            Record::DeliveredPayload(payload) => self.delivered.insert(payload),
+            Record::Registration(registration) => {
+                self.registrations
+                    .insert(registration.message.pubkey, registration);
+            }
+        }
+    }
+
+    fn received_bids(&self, filter: &BidFilter, limit: usize) -> Vec<StoredBid> {
+        self.received.select(filter).take(limit).cloned().collect()
+    }
+
+    fn delivered_payloads(
+        &self,
+        filter: &BidFilter,
+        order_by: Option<OrderBy>,
+        limit: usize,
+    ) -> Vec<StoredBid> {
+        let payloads = self.delivered.select(filter);
+        match order_by {
+            None => payloads.take(limit).cloned().collect(),
+            Some(OrderBy::Value) => first_by_key(payloads, limit, |payload| payload.trace.value),
+            Some(OrderBy::NegativeValue) => {
+                first_by_key(payloads, limit, |payload| Reverse(payload.trace.value))
+            }
+        }
+    }
+
+    fn prune(&mut self, slot: Slot) {
+        self.received.prune(slot);
+        self.delivered.prune(slot);
+    }
+
+    /// Records recreating this index when applied in order.
+    fn records(&self) -> impl Iterator<Item = Record> + '_ {
+        self.registrations
+            .values()
+            .cloned()
+            .map(Record::Registration)
+            .chain(
+                self.received
+                    .rows
+                    .values()
+                    .cloned()
+                    .map(Record::ReceivedBid),
+            )
+            .chain(
+                self.delivered
+                    .rows
+                    .values()
+                    .cloned()
+                    .map(Record::DeliveredPayload),
+            )
+    }
+}
+
+/// Storage kept in memory only, lost on restart.
+#[derive(Default)]
+pub struct MemoryStorage {
+    index: RwLock<Index>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn insert_received_bid(&self, bid: StoredBid) -> Result<(), Error> {
+        self.index.write().apply(Record::ReceivedBid(bid));
+        Ok(())
+    }
+
+    async fn insert_delivered_payload(&self, payload: StoredBid) -> Result<(), Error> {
+        self.index.write().apply(Record::DeliveredPayload(payload));
+        Ok(())
+    }
+
+    async fn insert_registration(
+        &self,
+        registration: SignedValidatorRegistrationData,
+    ) -> Result<(), Error> {
+        self.index.write().apply(Record::Registration(registration));
+        Ok(())
+    }
+
+    async fn received_bids(
+        &self,
+        filter: &BidFilter,
+        limit: usize,
+    ) -> Result<Vec<StoredBid>, Error> {
+        Ok(self.index.read().received_bids(filter, limit))
+    }
+
+    async fn delivered_payloads(
+        &self,
+        filter: &BidFilter,
+        order_by: Option<OrderBy>,
+        limit: usize,
+    ) -> Result<Vec<StoredBid>, Error> {
+        Ok(self
+            .index
+            .read()
+            .delivered_payloads(filter, order_by, limit))
+    }
+
+    async fn registration(
+        &self,
+        pubkey: &PublicKeyBytes,
+    ) -> Result<Option<SignedValidatorRegistrationData>, Error> {
+        Ok(self.index.read().registrations.get(pubkey).cloned())
+    }
+
+    async fn prune(&self, slot: Slot) -> Result<(), Error> {
+        self.index.write().prune(slot);
+        Ok(())
+    }
+}
+
+/// Storage persisted to an append-only log of JSON lines, with indexes kept in memory.
+///
+/// The log is replayed on [`FileStorage::open`] and rewritten without pruned entries on
+/// [`Storage::prune`]. Writes run on the blocking thread pool.
+pub struct FileStorage {
+    log: Arc<Log>,
+}
+
+/// The log file of a [`FileStorage`] and the index replaying it recreates.
+struct Log {
+    path: PathBuf,
+    index: RwLock<Index>,
+    writer: Mutex<BufWriter<File>>,
+}
+
+fn open_log(path: &Path) -> Result<BufWriter<File>, Error> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    Ok(BufWriter::new(file))
+}
+
+fn write_record(log: &mut impl Write, record: &Record) -> Result<(), Error> {
+    serde_json::to_writer(&mut *log, record)?;
+    log.write_all(b"\n")?;
+    Ok(())
+}
+
+impl Log {
+    fn append(&self, record: Record) -> Result<(), Error> {
+        let mut writer = self.writer.lock();
+        write_record(&mut *writer, &record)?;
+        writer.flush()?;
+        self.index.write().apply(record);
+        Ok(())
+    }
+
+    fn prune(&self, slot: Slot) -> Result<(), Error> {
+        let mut writer = self.writer.lock();
+        self.index.write().prune(slot);
+        self.compact(&mut writer)
+    }
+
+    /// Rewrite the log from the index.
+    fn compact(&self, writer: &mut BufWriter<File>) -> Result<(), Error> {
+        let tmp_path = self.path.with_extension("log.tmp");
+        let mut tmp = BufWriter::new(File::create(&tmp_path)?);
+        for record in self.index.read().records() {
+            write_record(&mut tmp, &record)?;
+        }
+        tmp.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        fs::rename(&tmp_path, &self.path)?;
+        *writer = open_log(&self.path)?;
+        Ok(())
+    }
+}
+
+impl FileStorage {
+    /// Open the log in `dir`, creating both if they do not exist.
+    pub fn open(dir: &Path) -> Result<Self, Error> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(LOG_FILE_NAME);
+
+        let mut index = Index::default();
+        if path.exists() {
+            let mut reader = BufReader::new(File::open(&path)?);
+            let mut line = String::new();
+            while reader.read_line(&mut line)? > 0 {
+                // A line without a newline is a write interrupted by a crash.
+                if !line.ends_with('\n') {
+                    warn!(path = ?path, "Ignoring incomplete record at end of storage log");
+                    break;
+                }
+                index.apply(serde_json::from_str(&line)?);
+                line.clear();
+            }
+        }
+
+        let log = Log {
+            writer: Mutex::new(open_log(&path)?),
+            path,
+            index: RwLock::new(index),
+        };
+        // Drop any incomplete record so appends start on a new line.
+        log.compact(&mut log.writer.lock())?;
+        Ok(Self { log: Arc::new(log) })
+    }
+
+    /// Run `f`, which writes to the log file, on the blocking thread pool.
+    async fn write<F>(&self, f: F) -> Result<(), Error>
+    where
+        F: FnOnce(&Log) -> Result<(), Error> + Send + 'static,
+    {
+        let log = self.log.clone();
+        tokio::task::spawn_blocking(move || f(&log))
+            .await
+            .map_err(|e| Error::Io(io::Error::other(e)))?
+    }
+}
+
+#[async_trait]
+impl Storage for FileStorage {
+    async fn insert_received_bid(&self, bid: StoredBid) -> Result<(), Error> {
+        self.write(|log| log.append(Record::ReceivedBid(bid))).await
+    }
+
+    async fn insert_delivered_payload(&self, payload: StoredBid) -> Result<(), Error> {
+        self.write(|log| log.append(Record::DeliveredPayload(payload)))
+            .await
+    }
+
+    async fn insert_registration(
+        &self,
+        registration: SignedValidatorRegistrationData,
+    ) -> Result<(), Error> {
+        self.write(|log| log.append(Record::Registration(registration)))
+            .await
+    }
+
+    async fn received_bids(
+        &self,
+        filter: &BidFilter,
+        limit: usize,
+    ) -> Result<Vec<StoredBid>, Error> {
+        Ok(self.log.index.read().received_bids(filter, limit))
+    }
+
+    async fn delivered_payloads(
+        &self,
+        filter: &BidFilter,
+        order_by: Option<OrderBy>,
+        limit: usize,
+    ) -> Result<Vec<StoredBid>, Error> {
+        Ok(self
+            .log
+            .index
+            .read()
+            .delivered_payloads(filter, order_by, limit))
+    }
+
+    async fn registration(
+        &self,
+        pubkey: &PublicKeyBytes,
+    ) -> Result<Option<SignedValidatorRegistrationData>, Error> {
+        Ok(self.log.index.read().registrations.get(pubkey).cloned())
+    }
+
+    async fn prune(&self, slot: Slot) -> Result<(), Error> {
+        self.write(move |log| log.prune(slot)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{block_hash, pubkey, registration, trace};
+
+    /// A fresh directory under the system's temporary directory.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "relay-storage-{name}-{}-{}",
+            std::process::id(),
+            rand::random::<u64>()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn bid(slot: u64, block: u8) -> StoredBid {
+        StoredBid {
+            trace: trace(slot, 1, 2, block, 100),
+            timestamp_ms: 1_000 * slot as i64,
+            ms_into_slot: Some(500),
+        }
+    }
+
+    /// Bids for blocks 1 to 5, in insertion order, each with its slot as block number:
+    /// `(slot, builder, proposer, block, value)`.
+    const BIDS: [(u64, u8, u8, u8, u64); 5] = [
+        (1, 1, 2, 1, 100),
+        (2, 3, 2, 2, 300),
+        (2, 1, 4, 3, 200),
+        (3, 3, 4, 4, 200),
+        (3, 1, 2, 5, 100),
+    ];
+
+    fn index() -> Index {
+        let mut index = Index::default();
+        for (slot, builder, proposer, block, value) in BIDS {
+            let mut trace = trace(slot, builder, proposer, block, value);
+            trace.block_number = slot;
+            let bid = StoredBid {
+                trace,
+                timestamp_ms: 0,
+                ms_into_slot: None,
+            };
+            index.apply(Record::ReceivedBid(bid.clone()));
+            index.apply(Record::DeliveredPayload(bid));
+        }
+        index
+    }
+
+    fn blocks(bids: &[StoredBid]) -> Vec<ExecutionBlockHash> {
+        bids.iter().map(|bid| bid.trace.block_hash).collect()
+    }
+
+    fn block_hashes(blocks: &[u8]) -> Vec<ExecutionBlockHash> {
+        blocks.iter().copied().map(block_hash).collect()
+    }
+
+    async fn received_bids(storage: &FileStorage) -> Vec<StoredBid> {
+        storage
+            .received_bids(&BidFilter::default(), usize::MAX)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn reopen() {
+        let dir = temp_dir("reopen");
+        let storage = FileStorage::open(&dir).unwrap();
+        storage.insert_received_bid(bid(1, 1)).await.unwrap();
+        storage.insert_received_bid(bid(2, 2)).await.unwrap();
+        storage.insert_delivered_payload(bid(2, 2)).await.unwrap();
+        storage.insert_registration(registration(2)).await.unwrap();
+        drop(storage);
+
+        // A record cut short by a crash is dropped.
+        let mut log = OpenOptions::new()
+            .append(true)
+            .open(dir.join(LOG_FILE_NAME))
+            .unwrap();
+        log.write_all(b"{\"type\":\"received_bid\"").unwrap();
+        drop(log);
+
+        let storage = FileStorage::open(&dir).unwrap();
+        assert_eq!(received_bids(&storage).await, vec![bid(2, 2), bid(1, 1)]);
+        assert_eq!(
+            storage
+                .delivered_payloads(&BidFilter::default(), None, usize::MAX)
+                .await
+                .unwrap(),
+            vec![bid(2, 2)]
+        );
+        assert_eq!(
+            storage.registration(&pubkey(2)).await.unwrap(),
+            Some(registration(2))
+        );
+
+        // Appends after the dropped record are readable.
+        storage.insert_received_bid(bid(3, 3)).await.unwrap();
+        drop(storage);
+        let storage = FileStorage::open(&dir).unwrap();
+        assert_eq!(received_bids(&storage).await.len(), 3);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn prune() {
+        let dir = temp_dir("prune");
+        let storage = FileStorage::open(&dir).unwrap();
+        for slot in 1..=3 {
+            storage
+                .insert_received_bid(bid(slot, slot as u8))
+                .await
+                .unwrap();
+        }
+        storage.insert_registration(registration(2)).await.unwrap();
+
+        storage.prune(Slot::new(2)).await.unwrap();
+        assert_eq!(received_bids(&storage).await, vec![bid(3, 3), bid(2, 2)]);
+        drop(storage);
+
+        let storage = FileStorage::open(&dir).unwrap();
+        assert_eq!(received_bids(&storage).await, vec![bid(3, 3), bid(2, 2)]);
+        assert_eq!(
+            storage.registration(&pubkey(2)).await.unwrap(),
+            Some(registration(2))
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn select() {
+        let index = index();
+        let cases = [
+            ("all", BidFilter::default(), vec![5, 4, 3, 2, 1]),
+            (
+                "slot",
+                BidFilter {
+                    slot: Some(Slot::new(2)),
+                    ..BidFilter::default()
+                },
+                vec![3, 2],
+            ),
+            (
+                "cursor",
+                BidFilter {
+                    cursor: Some(Slot::new(2)),
+                    ..BidFilter::default()
+                },
+                vec![3, 2, 1],
+            ),
+            (
+                "slot after cursor",
+                BidFilter {
+                    slot: Some(Slot::new(3)),
+                    cursor: Some(Slot::new(2)),
+                    ..BidFilter::default()
+                },
+                vec![],
+            ),
+            (
+                "block_hash",
+                BidFilter {
+                    block_hash: Some(block_hash(3)),
+                    ..BidFilter::default()
+                },
+                vec![3],
+            ),
+            (
+                "unknown block_hash",
+                BidFilter {
+                    block_hash: Some(block_hash(9)),
+                    ..BidFilter::default()
+                },
+                vec![],
+            ),
+            (
+                "block_number",
+                BidFilter {
+                    block_number: Some(2),
+                    ..BidFilter::default()
+                },
+                vec![3, 2],
+            ),
+            (
+                "proposer_pubkey",
+                BidFilter {
+                    proposer_pubkey: Some(pubkey(4)),
+                    ..BidFilter::default()
+                },
+                vec![4, 3],
+            ),
+            (
+                "builder_pubkey",
+                BidFilter {
+                    builder_pubkey: Some(pubkey(3)),
+                    ..BidFilter::default()
+                },
+                vec![4, 2],
+            ),
+            (
+                "block_number and builder_pubkey",
+                BidFilter {
+                    block_number: Some(3),
+                    builder_pubkey: Some(pubkey(1)),
+                    ..BidFilter::default()
+                },
+                vec![5],
+            ),
+            (
+                "builder_pubkey and cursor",
+                BidFilter {
+                    builder_pubkey: Some(pubkey(1)),
+                    cursor: Some(Slot::new(2)),
+                    ..BidFilter::default()
+                },
+                vec![3, 1],
+            ),
+            (
+                "proposer_pubkey and slot",
+                BidFilter {
+                    proposer_pubkey: Some(pubkey(2)),
+                    slot: Some(Slot::new(3)),
+                    ..BidFilter::default()
+                },
+                vec![5],
+            ),
+        ];
+        for (name, filter, expected) in cases {
+            let selected = index.delivered.select(&filter).cloned().collect::<Vec<_>>();
+            assert_eq!(blocks(&selected), block_hashes(&expected), "{name}");
+            assert_eq!(
+                blocks(&index.received_bids(&filter, usize::MAX)),
+                block_hashes(&expected),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn limit_and_order() {
+        let index = index();
+        let all = BidFilter::default();
+        let builder = BidFilter {
+            builder_pubkey: Some(pubkey(1)),
+            ..BidFilter::default()
+        };
+        // Payloads of equal value stay in descending slot order.
+        let cases = [
+            (&all, None, 2, vec![5, 4]),
+            (&all, None, 0, vec![]),
+            (&all, Some(OrderBy::Value), usize::MAX, vec![5, 1, 4, 3, 2]),
+            (&all, Some(OrderBy::Value), 3, vec![5, 1, 4]),
+            (&all, Some(OrderBy::NegativeValue), 2, vec![2, 4]),
+            (&all, Some(OrderBy::NegativeValue), 0, vec![]),
+            (
+                &builder,
+                Some(OrderBy::NegativeValue),
+                usize::MAX,
+                vec![3, 5, 1],
+            ),
+            (&builder, Some(OrderBy::Value), 1, vec![5]),
+        ];
+        for (filter, order_by, limit, expected) in cases {
+            assert_eq!(
+                blocks(&index.delivered_payloads(filter, order_by.clone(), limit)),
+                block_hashes(&expected),
+                "{filter:?} {order_by:?} {limit}"
+            );
+        }
+        assert_eq!(
+            blocks(&index.received_bids(&all, 3)),
+            block_hashes(&[5, 4, 3])
+        );
+    }
+
+    #[tokio::test]
+    async fn compaction() {
+        let dir = temp_dir("compaction");
+        let log_lines = || {
+            fs::read_to_string(dir.join(LOG_FILE_NAME))
+                .unwrap()
+                .lines()
+                .count()
+        };
+        let storage = FileStorage::open(&dir).unwrap();
+        for slot in 1..=3 {
+            storage
+                .insert_received_bid(bid(slot, slot as u8))
+                .await
+                .unwrap();
+            storage
+                .insert_delivered_payload(bid(slot, slot as u8))
+                .await
+                .unwrap();
+        }
+        // Only the newest registration of a validator is kept.
+        storage.insert_registration(registration(2)).await.unwrap();
+        storage.insert_registration(registration(2)).await.unwrap();
+        assert_eq!(log_lines(), 8);
+
+        // Pruning rewrites the log without pruned bids or replaced registrations.
+        storage.prune(Slot::new(3)).await.unwrap();
+        assert_eq!(log_lines(), 3);
+
+        // Appends go to the rewritten log.
+        storage.insert_received_bid(bid(4, 4)).await.unwrap();
+        assert_eq!(log_lines(), 4);
+        drop(storage);
+
+        let storage = FileStorage::open(&dir).unwrap();
+        assert_eq!(received_bids(&storage).await, vec![bid(4, 4), bid(3, 3)]);
+        assert_eq!(
+            storage
+                .delivered_payloads(&BidFilter::default(), None, usize::MAX)
+                .await
+                .unwrap(),
+            vec![bid(3, 3)]
+        );
+        assert_eq!(log_lines(), 4);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
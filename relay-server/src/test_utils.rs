@@ -0,0 +1,80 @@
+use builder_api_types::BlobsBundle;
+use relay_api_types::{
+    BidTraceV1, SubmitBlockRequest, SubmitBlockRequestCapella, SubmitBlockRequestDeneb,
+};
+use types::{
+    Address, EthSpec, ExecutionBlockHash, ExecutionPayloadCapella, ExecutionPayloadDeneb, Hash256,
+    PublicKeyBytes, Signature, SignedValidatorRegistrationData, Slot, Uint256,
+    ValidatorRegistrationData,
+};
+
+pub const GAS_LIMIT: u64 = 30_000_000;
+
+pub fn pubkey(byte: u8) -> PublicKeyBytes {
+    PublicKeyBytes::deserialize(&[byte; 48]).unwrap()
+}
+
+pub fn block_hash(byte: u8) -> ExecutionBlockHash {
+    ExecutionBlockHash::from_root(Hash256::repeat_byte(byte))
+}
+
+/// A bid of `value` wei by builder `builder` for block `block` at `slot`, paying proposer
+/// `proposer`. The fee recipient and gas limit match [`registration`].
+pub fn trace(slot: u64, builder: u8, proposer: u8, block: u8, value: u64) -> BidTraceV1 {
+    BidTraceV1 {
+        slot: Slot::new(slot),
+        parent_hash: block_hash(0),
+        block_hash: block_hash(block),
+        builder_pubkey: pubkey(builder),
+        proposer_pubkey: pubkey(proposer),
+        proposer_fee_recipient: Address::repeat_byte(proposer),
+        gas_limit: GAS_LIMIT,
+        gas_used: 0,
+        value: Uint256::from(value),
+        block_number: 1,
+        num_tx: 0,
+    }
+}
+
+pub fn registration(proposer: u8) -> SignedValidatorRegistrationData {
+    SignedValidatorRegistrationData {
+        message: ValidatorRegistrationData {
+            fee_recipient: Address::repeat_byte(proposer),
+            gas_limit: GAS_LIMIT,
+            timestamp: 0,
+            pubkey: pubkey(proposer),
+        },
+        signature: Signature::empty(),
+    }
+}
+
+pub fn capella_submission<E: EthSpec>(trace: BidTraceV1) -> SubmitBlockRequest<E> {
+    SubmitBlockRequest::Capella(SubmitBlockRequestCapella {
+        execution_payload: ExecutionPayloadCapella {
+            parent_hash: trace.parent_hash,
+            block_hash: trace.block_hash,
+            gas_limit: trace.gas_limit,
+            ..Default::default()
+        },
+        message: trace,
+        signature: Signature::empty(),
+    })
+}
+
+pub fn deneb_submission<E: EthSpec>(trace: BidTraceV1) -> SubmitBlockRequest<E> {
+    SubmitBlockRequest::Deneb(SubmitBlockRequestDeneb {
+        execution_payload: ExecutionPayloadDeneb {
+            parent_hash: trace.parent_hash,
+            block_hash: trace.block_hash,
+            gas_limit: trace.gas_limit,
+            ..Default::default()
+        },
+        blobs_bundle: BlobsBundle {
+            commitments: Default::default(),
+            proofs: Default::default(),
+            blobs: Default::default(),
+        },
+        message: trace,
+        signature: Signature::empty(),
+    })
+}
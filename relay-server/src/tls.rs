@@ -0,0 +1,173 @@
+use std::{fs::File, io, io::BufReader, net::SocketAddr, sync::Arc};
+
+use axum::Router;
+use axum_server::{tls_rustls::RustlsConfig, Handle};
+use rustls::ServerConfig;
+use tracing::{info, warn};
+
+use crate::config::TlsConfig;
+
+/// Build a rustls server config from the certificate chain and private key files in `tls`.
+///
+/// HTTP/2 is offered through ALPN only if `tls.http2` is set, otherwise clients use HTTP/1.1.
+pub fn server_config(tls: &TlsConfig) -> io::Result<ServerConfig> {
+    let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(&tls.cert_path)?))
+        .collect::<Result<Vec<_>, _>>()?;
+    let key = rustls_pemfile::private_key(&mut BufReader::new(File::open(&tls.key_path)?))?
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("no private key in {}", tls.key_path.display()),
+            )
+        })?;
+
+    let mut config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    config.alpn_protocols = if tls.http2 {
+        vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+    } else {
+        vec![b"http/1.1".to_vec()]
+    };
+    Ok(config)
+}
+
+/// Reload the certificate and key files in `tls` into `rustls_config`. If reading them fails the
+/// previous certificate is kept.
+pub fn reload(rustls_config: &RustlsConfig, tls: &TlsConfig) -> io::Result<()> {
+    rustls_config.reload_from_config(Arc::new(server_config(tls)?));
+    Ok(())
+}
+
+/// Reload the certificate and key into `rustls_config` every time the process receives SIGHUP.
+/// New connections use the reloaded certificate. If reloading fails the previous one is kept.
+#[cfg(unix)]
+pub async fn reload_on_sighup(rustls_config: RustlsConfig, tls: TlsConfig) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut sighup = match signal(SignalKind::hangup()) {
+        Ok(sighup) => sighup,
+        Err(e) => {
+            warn!(error = ?e, "Failed to listen for SIGHUP, certificates will not be reloaded");
+            return;
+        }
+    };
+
+    while sighup.recv().await.is_some() {
+        match reload(&rustls_config, &tls) {
+            Ok(()) => info!(cert_path = ?tls.cert_path, "Reloaded TLS certificate"),
+            Err(e) => {
+                warn!(error = ?e, "Failed to reload TLS certificate, keeping the previous one")
+            }
+        }
+    }
+}
+
+/// Serve `router` over HTTPS on `address` until `handle` shuts the server down. On Unix the
+/// certificate is reloaded on SIGHUP.
+pub async fn serve(
+    router: Router,
+    address: SocketAddr,
+    tls: TlsConfig,
+    handle: Handle,
+) -> io::Result<()> {
+    let rustls_config = RustlsConfig::from_config(Arc::new(server_config(&tls)?));
+
+    #[cfg(unix)]
+    tokio::spawn(reload_on_sighup(rustls_config.clone(), tls));
+    #[cfg(not(unix))]
+    drop(tls);
+
+    axum_server::bind_rustls(address, rustls_config)
+        .handle(handle)
+        .serve(router.into_make_service())
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::*;
+
+    fn testdata(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata")
+            .join(name)
+    }
+
+    fn tls_config(name: &str, http2: bool) -> TlsConfig {
+        TlsConfig {
+            cert_path: testdata(&format!("{name}.cert.pem")),
+            key_path: testdata(&format!("{name}.key.pem")),
+            http2,
+        }
+    }
+
+    #[test]
+    fn alpn() {
+        let config = server_config(&tls_config("localhost", false)).unwrap();
+        assert_eq!(config.alpn_protocols, vec![b"http/1.1".to_vec()]);
+
+        let config = server_config(&tls_config("localhost", true)).unwrap();
+        assert_eq!(
+            config.alpn_protocols,
+            vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+        );
+    }
+
+    #[test]
+    fn invalid_files() {
+        let tls = TlsConfig {
+            key_path: testdata("missing.key.pem"),
+            ..tls_config("localhost", false)
+        };
+        let e = server_config(&tls).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::NotFound);
+
+        // A certificate where the key is expected.
+        let tls = TlsConfig {
+            key_path: testdata("localhost.cert.pem"),
+            ..tls_config("localhost", false)
+        };
+        let e = server_config(&tls).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert!(e.to_string().starts_with("no private key in"), "{e}");
+    }
+
+    #[test]
+    fn reload_certificate() {
+        let dir = std::env::temp_dir().join(format!(
+            "relay-tls-{}-{}",
+            std::process::id(),
+            rand::random::<u64>()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let tls = TlsConfig {
+            cert_path: dir.join("cert.pem"),
+            key_path: dir.join("key.pem"),
+            http2: true,
+        };
+        fs::copy(testdata("localhost.cert.pem"), &tls.cert_path).unwrap();
+        fs::copy(testdata("localhost.key.pem"), &tls.key_path).unwrap();
+
+        let rustls_config = RustlsConfig::from_config(Arc::new(server_config(&tls).unwrap()));
+        let initial = rustls_config.get_inner();
+
+        // A renewed certificate is picked up.
+        fs::copy(testdata("renewed.cert.pem"), &tls.cert_path).unwrap();
+        fs::copy(testdata("renewed.key.pem"), &tls.key_path).unwrap();
+        reload(&rustls_config, &tls).unwrap();
+        let renewed = rustls_config.get_inner();
+        assert!(!Arc::ptr_eq(&initial, &renewed));
+        assert_eq!(renewed.alpn_protocols, initial.alpn_protocols);
+
+        // A failed reload keeps the previous certificate.
+        fs::remove_file(&tls.key_path).unwrap();
+        assert!(reload(&rustls_config, &tls).is_err());
+        assert!(Arc::ptr_eq(&renewed, &rustls_config.get_inner()));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,262 @@
+use relay_api_types::{BidTraceV1, ErrorResponse, Response};
+use types::{Address, PublicKeyBytes, SignedValidatorRegistrationData, Slot};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    /// No proposer is known for the slot.
+    UnknownProposer {
+        slot: Slot,
+    },
+    /// The proposer has not registered with the relay.
+    NotRegistered {
+        proposer_pubkey: PublicKeyBytes,
+    },
+    ProposerPubkeyMismatch {
+        expected: PublicKeyBytes,
+        received: PublicKeyBytes,
+    },
+    FeeRecipientMismatch {
+        expected: Address,
+        received: Address,
+    },
+    GasLimitMismatch {
+        expected: u64,
+        received: u64,
+    },
+}
+
+impl ValidationError {
+    /// The HTTP status the error is served with, distinct for every violation so builders can
+    /// tell them apart without parsing the message.
+    pub fn code(&self) -> u16 {
+        match self {
+            ValidationError::UnknownProposer { .. } => 400,
+            ValidationError::NotRegistered { .. } => 403,
+            ValidationError::ProposerPubkeyMismatch { .. } => 409,
+            ValidationError::FeeRecipientMismatch { .. } => 412,
+            ValidationError::GasLimitMismatch { .. } => 422,
+        }
+    }
+
+    pub fn to_response<T>(&self) -> Response<T> {
+        let message = match self {
+            ValidationError::UnknownProposer { slot } => {
+                format!("no proposer known for slot {slot}")
+            }
+            ValidationError::NotRegistered { proposer_pubkey } => {
+                format!("proposer {proposer_pubkey} is not registered")
+            }
+            ValidationError::ProposerPubkeyMismatch { expected, received } => {
+                format!("incorrect proposer_pubkey: expected {expected}, received {received}")
+            }
+            ValidationError::FeeRecipientMismatch { expected, received } => format!(
+                "incorrect proposer_fee_recipient: expected {expected:?}, received {received:?}"
+            ),
+            ValidationError::GasLimitMismatch { expected, received } => {
+                format!("incorrect gas_limit: expected {expected}, received {received}")
+            }
+        };
+        Response::Error(ErrorResponse {
+            code: self.code(),
+            message,
+            stacktraces: None,
+        })
+    }
+}
+
+/// The gas limit of a block with parent gas limit `parent_gas_limit` when moving as far towards
+/// `target` as the protocol allows.
+pub fn expected_gas_limit(parent_gas_limit: u64, target: u64) -> u64 {
+    let max_delta = (parent_gas_limit / 1024).saturating_sub(1);
+    if target > parent_gas_limit {
+        target.min(parent_gas_limit + max_delta)
+    } else {
+        target.max(parent_gas_limit.saturating_sub(max_delta))
+    }
+}
+
+/// Check that a bid is for the proposer scheduled for its slot, pays that proposer's registered
+/// fee recipient, and moves the gas limit towards the registered target.
+///
+/// `proposer_pubkey` is the proposer scheduled for `trace.slot` and `registration` its
+/// registration, if either is known. The gas limit is only checked if the parent block's
+/// `parent_gas_limit` is known.
+pub fn validate_against_registration(
+    trace: &BidTraceV1,
+    proposer_pubkey: Option<&PublicKeyBytes>,
+    registration: Option<&SignedValidatorRegistrationData>,
+    parent_gas_limit: Option<u64>,
+) -> Result<(), ValidationError> {
+    let proposer_pubkey =
+        proposer_pubkey.ok_or(ValidationError::UnknownProposer { slot: trace.slot })?;
+    if trace.proposer_pubkey != *proposer_pubkey {
+        return Err(ValidationError::ProposerPubkeyMismatch {
+            expected: *proposer_pubkey,
+            received: trace.proposer_pubkey,
+        });
+    }
+
+    let registration = registration.ok_or(ValidationError::NotRegistered {
+        proposer_pubkey: *proposer_pubkey,
+    })?;
+    if trace.proposer_fee_recipient != registration.message.fee_recipient {
+        return Err(ValidationError::FeeRecipientMismatch {
+            expected: registration.message.fee_recipient,
+            received: trace.proposer_fee_recipient,
+        });
+    }
+
+    if let Some(parent_gas_limit) = parent_gas_limit {
+        let expected = expected_gas_limit(parent_gas_limit, registration.message.gas_limit);
+        if trace.gas_limit != expected {
+            return Err(ValidationError::GasLimitMismatch {
+                expected,
+                received: trace.gas_limit,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{pubkey, registration, trace, GAS_LIMIT};
+
+    const PROPOSER: u8 = 2;
+
+    #[test]
+    fn gas_limit_steps() {
+        // The gas limit moves by less than 1/1024 of the parent's per block.
+        assert_eq!(expected_gas_limit(30_000_000, 36_000_000), 30_029_295);
+        assert_eq!(expected_gas_limit(30_000_000, 30_010_000), 30_010_000);
+        assert_eq!(expected_gas_limit(30_000_000, 20_000_000), 29_970_705);
+        assert_eq!(expected_gas_limit(30_000_000, 29_990_000), 29_990_000);
+        assert_eq!(expected_gas_limit(30_000_000, 30_000_000), 30_000_000);
+        // Too small a parent gas limit to move at all.
+        assert_eq!(expected_gas_limit(1_000, 2_000), 1_000);
+        assert_eq!(expected_gas_limit(0, 1), 0);
+    }
+
+    #[test]
+    fn registration_mismatches() {
+        let trace = trace(10, 1, PROPOSER, 3, 100);
+        let registration = registration(PROPOSER);
+
+        assert_eq!(
+            validate_against_registration(
+                &trace,
+                Some(&pubkey(PROPOSER)),
+                Some(&registration),
+                None
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            validate_against_registration(
+                &trace,
+                Some(&pubkey(PROPOSER)),
+                Some(&registration),
+                Some(GAS_LIMIT)
+            ),
+            Ok(())
+        );
+
+        assert_eq!(
+            validate_against_registration(&trace, None, Some(&registration), None),
+            Err(ValidationError::UnknownProposer { slot: trace.slot })
+        );
+        assert_eq!(
+            validate_against_registration(&trace, Some(&pubkey(3)), Some(&registration), None),
+            Err(ValidationError::ProposerPubkeyMismatch {
+                expected: pubkey(3),
+                received: pubkey(PROPOSER),
+            })
+        );
+        assert_eq!(
+            validate_against_registration(&trace, Some(&pubkey(PROPOSER)), None, None),
+            Err(ValidationError::NotRegistered {
+                proposer_pubkey: pubkey(PROPOSER),
+            })
+        );
+
+        let mut other_fee_recipient = trace.clone();
+        other_fee_recipient.proposer_fee_recipient = Address::repeat_byte(9);
+        assert_eq!(
+            validate_against_registration(
+                &other_fee_recipient,
+                Some(&pubkey(PROPOSER)),
+                Some(&registration),
+                None
+            ),
+            Err(ValidationError::FeeRecipientMismatch {
+                expected: registration.message.fee_recipient,
+                received: Address::repeat_byte(9),
+            })
+        );
+
+        // The registered target is above the parent's gas limit, so the gas limit must rise.
+        assert_eq!(
+            validate_against_registration(
+                &trace,
+                Some(&pubkey(PROPOSER)),
+                Some(&registration),
+                Some(GAS_LIMIT - 1_000_000)
+            ),
+            Err(ValidationError::GasLimitMismatch {
+                expected: expected_gas_limit(GAS_LIMIT - 1_000_000, GAS_LIMIT),
+                received: GAS_LIMIT,
+            })
+        );
+    }
+
+    #[test]
+    fn error_responses() {
+        let errors = [
+            (
+                ValidationError::UnknownProposer {
+                    slot: Slot::new(10),
+                },
+                400,
+            ),
+            (
+                ValidationError::NotRegistered {
+                    proposer_pubkey: pubkey(PROPOSER),
+                },
+                403,
+            ),
+            (
+                ValidationError::ProposerPubkeyMismatch {
+                    expected: pubkey(3),
+                    received: pubkey(PROPOSER),
+                },
+                409,
+            ),
+            (
+                ValidationError::FeeRecipientMismatch {
+                    expected: Address::repeat_byte(PROPOSER),
+                    received: Address::repeat_byte(9),
+                },
+                412,
+            ),
+            (
+                ValidationError::GasLimitMismatch {
+                    expected: GAS_LIMIT,
+                    received: GAS_LIMIT + 1,
+                },
+                422,
+            ),
+        ];
+
+        let mut messages = Vec::new();
+        for (error, code) in &errors {
+            let Response::Error(response) = error.to_response::<()>() else {
+                panic!("validation error without an error response");
+            };
+            assert_eq!(response.code, *code);
+            assert!(!messages.contains(&response.message));
+            messages.push(response.message);
+        }
+    }
+}